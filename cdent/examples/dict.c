@@ -0,0 +1,36 @@
+#include "cdent.h"
+#include <stdio.h>
+#include <string.h>
+
+const char* dent_data = "{ name: dict version: \"0.0.1\" stable: false }";
+
+int main(int argc, char* argv[]) {
+    (void)argc;
+    (void)argv;
+
+    dent_init();
+
+    dent_value_t* value = dent_parse(dent_data, strlen(dent_data));
+
+    if (value == NULL) {
+        printf("Error parsing dent data\n");
+        return EXIT_FAILURE;
+    }
+
+    // Entries are visited in the order they appear in the source
+    for (uintptr_t i = 0; i < dent_len(value); i++) {
+        char* key = dent_dict_key(value, i);
+        char* entry = dent_to_str(dent_dict_value(value, i));
+
+        printf("%s: %s\n", key, entry);
+
+        dent_free_str(entry);
+        dent_free_str(key);
+    }
+
+    dent_free(value);
+
+    dent_shutdown();
+
+    return EXIT_SUCCESS;
+}
@@ -52,3 +52,7 @@ dent_value_t *dent_list_get(dent_value_t *value, uintptr_t index);
 dent_value_t *dent_dict_get(dent_value_t *value, const char *key);
 
 char *dent_to_str(const dent_value_t *value);
+
+char *dent_dict_key(const dent_value_t *value, uintptr_t index);
+
+dent_value_t *dent_dict_value(const dent_value_t *value, uintptr_t index);
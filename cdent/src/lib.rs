@@ -24,8 +24,9 @@ pub extern "C" fn dent_shutdown() {
 pub extern "C" fn dent_parse(input: *const c_char, len: usize) -> *mut Value<'static> {
     let input = unsafe { std::slice::from_raw_parts(input as *const u8, len) };
     let input = std::str::from_utf8(input).unwrap();
-    let parser = unsafe { DENT.as_mut().unwrap() };
-    let value = parser.parse(input).unwrap();
+    let parser = unsafe { (*std::ptr::addr_of!(DENT)).as_ref().unwrap() };
+    // The input buffer belongs to the caller, so the value can't borrow from it
+    let value = parser.parse(input).unwrap().into_owned();
     Box::into_raw(Box::new(value))
 }
 
@@ -33,7 +34,7 @@ pub extern "C" fn dent_parse(input: *const c_char, len: usize) -> *mut Value<'st
 pub extern "C" fn dent_parse_file(path: *const c_char) -> *mut Value<'static> {
     let path = unsafe { CStr::from_ptr(path) };
     let path = path.to_str().unwrap();
-    let parser = unsafe { DENT.as_mut().unwrap() };
+    let parser = unsafe { (*std::ptr::addr_of!(DENT)).as_ref().unwrap() };
     let value = parser.parse_file(path).unwrap();
     Box::into_raw(Box::new(value))
 }
@@ -179,6 +180,55 @@ pub extern "C" fn dent_dict_get(
 #[no_mangle]
 pub extern "C" fn dent_to_str(value: *const Value<'static>) -> *mut c_char {
     let value = unsafe { &*value };
-    let value = value.to_string();
-    CString::new(value).unwrap().into_raw()
+    let mut out = String::new();
+    write_plain(&mut out, value);
+    CString::new(out).unwrap().into_raw()
+}
+
+// `dent_to_str` keeps the format it had before `Value`'s `Display` moved to
+// the `Emitter`: strings are not quoted, and lists and dictionaries are
+// always padded, as in `[ ]`
+fn write_plain(out: &mut String, value: &Value) {
+    match value {
+        Value::List(l) => {
+            out.push('[');
+            for v in l.iter() {
+                out.push(' ');
+                write_plain(out, v);
+            }
+            out.push_str(" ]");
+        }
+        Value::Dict(d) => {
+            out.push('{');
+            for (k, v) in d.iter() {
+                out.push(' ');
+                out.push_str(k);
+                out.push_str(": ");
+                write_plain(out, v);
+            }
+            out.push_str(" }");
+        }
+        Value::Str(s) => out.push_str(s),
+        Value::Float(f) => out.push_str(&f.to_string()),
+        // Scalars other than strings and floats print the same as they did
+        v => out.push_str(&v.to_string()),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn dent_dict_key(value: *const Value<'static>, index: usize) -> *mut c_char {
+    let value = unsafe { &*value };
+    let (key, _) = value.as_dict().unwrap().get_index(index).unwrap();
+    CString::new(key.as_ref()).unwrap().into_raw()
+}
+
+#[no_mangle]
+pub extern "C" fn dent_dict_value(
+    value: *const Value<'static>,
+    index: usize,
+) -> *mut Value<'static> {
+    let value = unsafe { &*value };
+    let (_, value) = value.as_dict().unwrap().get_index(index).unwrap();
+
+    value as *const Value<'static> as *mut Value<'static>
 }
@@ -0,0 +1,34 @@
+use std::fmt::Display;
+
+use crate::Location;
+
+/// The result of a conversion from or to another format that succeeded, but
+/// had to change the data to fit.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Converted<T> {
+    /// The converted data
+    pub value: T,
+    /// Everything that did not convert as it was, in document order
+    pub warnings: Vec<ConversionWarning>,
+}
+
+/// A construct that had to be changed or left out to convert data from or to
+/// another format, such as a YAML alias that was expanded into a copy.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ConversionWarning {
+    /// The key path of the value the warning is about, empty for the root
+    pub path: String,
+    pub message: String,
+    /// Where the construct is in the source, if the source was parsed
+    pub location: Option<Location>,
+}
+
+impl Display for ConversionWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
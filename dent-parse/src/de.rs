@@ -0,0 +1,521 @@
+//! Deserializing Rust types from Dent with `serde`.
+
+use std::{borrow::Cow, path::Path};
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
+
+use crate::{key_path::KeyPath, Dent, Dict, Error, Result, Value};
+
+/// Deserializes an instance of `T` from a Dent string.
+///
+/// The string is parsed with `Dent::default`, so the built-in functions are
+/// available. Strings in the input that contain no escape sequences can be
+/// deserialized into `&str` fields without copying.
+///
+/// Errors carry the key path of the value that failed to deserialize.
+///
+/// # Examples
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct Character<'a> {
+///     name: &'a str,
+///     age: u32,
+/// }
+///
+/// let character: Character = dent_parse::from_str("{ name: Mario age: 35 }").unwrap();
+/// assert_eq!(character, Character { name: "Mario", age: 35 });
+///
+/// let error = dent_parse::from_str::<Vec<Character>>("[ { name: Luigi age: old } ]");
+/// assert_eq!(
+///     error.unwrap_err().to_string(),
+///     "[0].age: invalid type: string \"old\", expected u32"
+/// );
+/// ```
+pub fn from_str<'de, T: de::Deserialize<'de>>(input: &'de str) -> Result<T> {
+    let value = Dent::default().parse(input)?;
+    T::deserialize(ValueDeserializer::new(value, KeyPath::Root))
+}
+
+/// Deserializes an instance of `T` from a Dent file.
+///
+/// The file is parsed with `Dent::default`, so the built-in functions are
+/// available.
+pub fn from_file<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<T> {
+    let value = Dent::default().parse_file(path)?;
+    T::deserialize(ValueDeserializer::new(value, KeyPath::Root))
+}
+
+/// Deserializes an instance of `T` from a parsed `Value`.
+///
+/// Strings in `T` can borrow from the value.
+pub fn from_value<'de, T: de::Deserialize<'de>>(value: &'de Value<'_>) -> Result<T> {
+    T::deserialize(ValueDeserializer::new(value.as_borrowed(), KeyPath::Root))
+}
+
+impl de::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::Serde {
+            path: String::new(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+struct ValueDeserializer<'de, 'p> {
+    value: Value<'de>,
+    path: KeyPath<'p>,
+}
+
+impl<'de, 'p> ValueDeserializer<'de, 'p> {
+    fn new(value: Value<'de>, path: KeyPath<'p>) -> Self {
+        ValueDeserializer { value, path }
+    }
+
+    /// Sets the path of an error that has none yet, which is the case for
+    /// errors created by visitors.
+    fn at_path(path: KeyPath<'p>, error: Error) -> Error {
+        match error {
+            Error::Serde {
+                path: error_path,
+                message,
+            } if error_path.is_empty() => Error::Serde {
+                path: path.to_string(),
+                message,
+            },
+            error => error,
+        }
+    }
+
+    fn invalid_type(&self, exp: &dyn de::Expected) -> Error {
+        let unexpected = match &self.value {
+            Value::None => de::Unexpected::Unit,
+            Value::Str(s) => de::Unexpected::Str(s),
+            Value::Int(i) => de::Unexpected::Signed(*i),
+            Value::Float(f) => de::Unexpected::Float(*f),
+            Value::Bool(b) => de::Unexpected::Bool(*b),
+            Value::List(_) => de::Unexpected::Seq,
+            Value::Dict(_) => de::Unexpected::Map,
+        };
+        de::Error::invalid_type(unexpected, exp)
+    }
+
+    fn deserialize_inner<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::None => visitor.visit_unit(),
+            Value::Str(Cow::Borrowed(s)) => visitor.visit_borrowed_str(s),
+            Value::Str(Cow::Owned(s)) => visitor.visit_string(s),
+            Value::Int(i) => visitor.visit_i64(i),
+            Value::Float(f) => visitor.visit_f64(f),
+            Value::Bool(b) => visitor.visit_bool(b),
+            Value::List(l) => {
+                let len = l.len();
+                let mut seq = SeqDeserializer {
+                    iter: l.into_iter(),
+                    index: 0,
+                    path: self.path,
+                };
+                let value = visitor.visit_seq(&mut seq)?;
+                if seq.iter.len() == 0 {
+                    Ok(value)
+                } else {
+                    Err(de::Error::invalid_length(len, &"fewer elements in list"))
+                }
+            }
+            Value::Dict(d) => {
+                let len = d.len();
+                let mut map = MapDeserializer {
+                    iter: d.into_iter(),
+                    entry: None,
+                    path: self.path,
+                };
+                let value = visitor.visit_map(&mut map)?;
+                if map.iter.len() == 0 {
+                    Ok(value)
+                } else {
+                    Err(de::Error::invalid_length(
+                        len,
+                        &"fewer entries in dictionary",
+                    ))
+                }
+            }
+        }
+    }
+}
+
+macro_rules! forward_to_any {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                self.deserialize_any(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de, 'p> de::Deserializer<'de> for ValueDeserializer<'de, 'p> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let path = self.path;
+        self.deserialize_inner(visitor)
+            .map_err(|e| Self::at_path(path, e))
+    }
+
+    forward_to_any! {
+        deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64
+        deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64
+        deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char deserialize_str
+        deserialize_string deserialize_bytes deserialize_byte_buf deserialize_seq
+        deserialize_map deserialize_identifier
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::None => visitor.visit_none(),
+            _ => {
+                let path = self.path;
+                visitor.visit_some(self).map_err(|e| Self::at_path(path, e))
+            }
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::None => visitor.visit_unit(),
+            _ => Err(Self::at_path(self.path, self.invalid_type(&visitor))),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    /// Enums are represented as a string for unit variants, or a dictionary
+    /// with a single entry mapping the variant name to its content.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        let path = self.path;
+        let result = match self.value {
+            Value::Str(variant) => visitor.visit_enum(EnumDeserializer {
+                variant,
+                content: None,
+                path,
+            }),
+            Value::Dict(d) if d.len() == 1 => {
+                let (variant, content) = d.into_iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    content: Some(content),
+                    path,
+                })
+            }
+            _ => Err(self.invalid_type(&"a string or a dictionary with a single key")),
+        };
+        result.map_err(|e| Self::at_path(path, e))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+}
+
+struct SeqDeserializer<'de, 'p> {
+    iter: std::vec::IntoIter<Value<'de>>,
+    index: usize,
+    path: KeyPath<'p>,
+}
+
+impl<'de, 'p> SeqAccess<'de> for SeqDeserializer<'de, 'p> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        match self.iter.next() {
+            Some(value) => {
+                let path = KeyPath::Index(&self.path, self.index);
+                self.index += 1;
+                seed.deserialize(ValueDeserializer::new(value, path))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct MapDeserializer<'de, 'p> {
+    iter: <Dict<'de> as IntoIterator>::IntoIter,
+    entry: Option<(Cow<'de, str>, Value<'de>)>,
+    path: KeyPath<'p>,
+}
+
+impl<'de, 'p> MapAccess<'de> for MapDeserializer<'de, 'p> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                let result = seed.deserialize(KeyDeserializer {
+                    key: key.clone(),
+                    path: KeyPath::Key(&self.path, &key),
+                });
+                self.entry = Some((key, value));
+                result.map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let (key, value) = self
+            .entry
+            .take()
+            .ok_or_else(|| <Error as de::Error>::custom("value requested before key"))?;
+        seed.deserialize(ValueDeserializer::new(
+            value,
+            KeyPath::Key(&self.path, &key),
+        ))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Deserializes a dictionary key. Keys are always strings, but the
+/// serializer writes integer and boolean map keys as their string form, so
+/// they are parsed back when the key type asks for them.
+struct KeyDeserializer<'de, 'p> {
+    key: Cow<'de, str>,
+    path: KeyPath<'p>,
+}
+
+impl<'de, 'p> KeyDeserializer<'de, 'p> {
+    fn parse<T: std::str::FromStr>(&self, exp: &dyn de::Expected) -> Result<T> {
+        self.key.parse().map_err(|_| {
+            ValueDeserializer::at_path(
+                self.path,
+                de::Error::invalid_value(de::Unexpected::Str(&self.key), exp),
+            )
+        })
+    }
+}
+
+macro_rules! parse_key {
+    ($($method:ident $visit:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                let key = self.parse(&visitor)?;
+                let path = self.path;
+                visitor
+                    .$visit(key)
+                    .map_err(|e| ValueDeserializer::at_path(path, e))
+            }
+        )*
+    };
+}
+
+impl<'de, 'p> de::Deserializer<'de> for KeyDeserializer<'de, 'p> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        de::Deserializer::deserialize_any(
+            ValueDeserializer::new(Value::Str(self.key), self.path),
+            visitor,
+        )
+    }
+
+    parse_key! {
+        deserialize_bool visit_bool deserialize_i8 visit_i8 deserialize_i16 visit_i16
+        deserialize_i32 visit_i32 deserialize_i64 visit_i64 deserialize_i128 visit_i128
+        deserialize_u8 visit_u8 deserialize_u16 visit_u16 deserialize_u32 visit_u32
+        deserialize_u64 visit_u64 deserialize_u128 visit_u128
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let path = self.path;
+        visitor
+            .visit_some(self)
+            .map_err(|e| ValueDeserializer::at_path(path, e))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        de::Deserializer::deserialize_enum(
+            ValueDeserializer::new(Value::Str(self.key), self.path),
+            name,
+            variants,
+            visitor,
+        )
+    }
+
+    forward_to_any! {
+        deserialize_f32 deserialize_f64 deserialize_char deserialize_str deserialize_string
+        deserialize_bytes deserialize_byte_buf deserialize_unit deserialize_seq deserialize_map
+        deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+}
+
+struct EnumDeserializer<'de, 'p> {
+    variant: Cow<'de, str>,
+    content: Option<Value<'de>>,
+    path: KeyPath<'p>,
+}
+
+impl<'de, 'p> EnumAccess<'de> for EnumDeserializer<'de, 'p> {
+    type Error = Error;
+    type Variant = VariantDeserializer<'de, 'p>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant)> {
+        let variant = seed.deserialize(ValueDeserializer::new(
+            Value::Str(self.variant.clone()),
+            self.path,
+        ))?;
+        Ok((
+            variant,
+            VariantDeserializer {
+                variant: self.variant,
+                content: self.content,
+                path: self.path,
+            },
+        ))
+    }
+}
+
+struct VariantDeserializer<'de, 'p> {
+    variant: Cow<'de, str>,
+    content: Option<Value<'de>>,
+    path: KeyPath<'p>,
+}
+
+impl<'de, 'p> VariantDeserializer<'de, 'p> {
+    fn content<T>(self, f: impl FnOnce(ValueDeserializer<'de, '_>) -> Result<T>) -> Result<T> {
+        match self.content {
+            Some(content) => f(ValueDeserializer::new(
+                content,
+                KeyPath::Key(&self.path, &self.variant),
+            )),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"a variant with content",
+            )),
+        }
+    }
+}
+
+impl<'de, 'p> VariantAccess<'de> for VariantDeserializer<'de, 'p> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        match self.content {
+            None | Some(Value::None) => Ok(()),
+            Some(_) => Err(de::Error::invalid_type(
+                de::Unexpected::NewtypeVariant,
+                &"a unit variant",
+            )),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        self.content(|d| seed.deserialize(d))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.content(|d| de::Deserializer::deserialize_any(d, visitor))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.content(|d| de::Deserializer::deserialize_any(d, visitor))
+    }
+}
@@ -0,0 +1,411 @@
+//! Human-readable diagnostics for Dent errors.
+//!
+//! A `Diagnostic` is a message with an optional source location, labels, notes
+//! and help, which can be rendered against the source text as an annotated
+//! snippet, in the style of `rustc`:
+//!
+//! ```text
+//! error: expected `:` after dictionary key `name`
+//!  --> characters.dent:2:10
+//!   |
+//! 2 |     name Mario
+//!   |          ^^^^^ expected `:`, found a string
+//!   |
+//!   = help: dictionary entries are written as `key: value`
+//! ```
+//!
+//! # Examples
+//! ```
+//! use dent_parse::{diagnostics::Diagnostic, Dent};
+//!
+//! let source = "{\n    name Mario\n}";
+//! let error = Dent::default().parse(source).unwrap_err();
+//!
+//! let rendered = Diagnostic::from(&error).render(source);
+//! assert!(rendered.starts_with("error: expected `:` after dictionary key `name`"));
+//! assert!(rendered.contains("2 |     name Mario"));
+//! ```
+
+use std::fmt::{Display, Write};
+
+use crate::{tokenizer::describe_token, Error, Location};
+
+/// How serious a diagnostic is.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Note => write!(f, "note"),
+        }
+    }
+}
+
+/// A source location annotated with a message.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Label {
+    pub location: Location,
+    pub message: String,
+}
+
+/// A message about a Dent source, optionally pointing at a location in it.
+///
+/// The primary label is underlined with `^`, secondary labels with `-`.
+/// Secondary labels in a different file than the primary label are rendered
+/// as notes instead of snippets, since only one source is available.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub primary: Option<Label>,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+impl Diagnostic {
+    /// Creates a new diagnostic with no location.
+    pub fn new(severity: Severity, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            primary: None,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    /// Creates a new error diagnostic.
+    pub fn error(message: impl Into<String>) -> Diagnostic {
+        Self::new(Severity::Error, message)
+    }
+
+    /// Creates a new warning diagnostic.
+    pub fn warning(message: impl Into<String>) -> Diagnostic {
+        Self::new(Severity::Warning, message)
+    }
+
+    /// Sets the primary location of the diagnostic.
+    pub fn with_primary(mut self, location: Location, message: impl Into<String>) -> Self {
+        self.primary = Some(Label {
+            location,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Adds a secondary location to the diagnostic.
+    pub fn with_secondary(mut self, location: Location, message: impl Into<String>) -> Self {
+        self.secondary.push(Label {
+            location,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Adds a note to the diagnostic.
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Adds a help message to the diagnostic.
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    /// Renders the diagnostic as an annotated snippet of `source`.
+    ///
+    /// `source` should be the text the primary location refers to. If a
+    /// location is out of range of the source, or does not start and end on
+    /// character boundaries in it, as when the file changed since it was
+    /// parsed, its snippet is skipped.
+    pub fn render(&self, source: &str) -> String {
+        let mut out = String::new();
+        let _ = self.write(&mut out, source);
+        out
+    }
+
+    fn write(&self, out: &mut String, source: &str) -> std::fmt::Result {
+        writeln!(out, "{}: {}", self.severity, self.message)?;
+
+        let mut notes = Vec::new();
+        let mut labels = Vec::new();
+
+        if let Some(primary) = &self.primary {
+            labels.push((primary, '^'));
+            for label in self.secondary.iter() {
+                if label.location.file == primary.location.file {
+                    labels.push((label, '-'));
+                } else {
+                    notes.push(format!("{}: {}", label.location, label.message));
+                }
+            }
+        } else {
+            for label in self.secondary.iter() {
+                notes.push(format!("{}: {}", label.location, label.message));
+            }
+        }
+
+        labels.retain(|(label, _)| {
+            let span = label.location.span;
+            source.is_char_boundary(span.start) && source.is_char_boundary(span.end)
+        });
+        labels.sort_by_key(|(label, _)| label.location.span.start);
+
+        let gutter = labels
+            .iter()
+            .map(|(label, _)| label.location.span.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        if let Some(primary) = &self.primary {
+            writeln!(out, "{:gutter$}--> {}", "", primary.location)?;
+        }
+
+        if !labels.is_empty() {
+            writeln!(out, "{:gutter$} |", "")?;
+            let mut previous_line = None;
+            for (label, underline) in labels.iter() {
+                let line = label.location.span.line;
+                Self::write_label(
+                    out,
+                    source,
+                    label,
+                    *underline,
+                    gutter,
+                    previous_line != Some(line),
+                )?;
+                previous_line = Some(line);
+            }
+        }
+
+        notes.extend(self.notes.iter().map(|n| format!("note: {}", n)));
+        notes.extend(self.help.iter().map(|h| format!("help: {}", h)));
+
+        if !notes.is_empty() {
+            writeln!(out, "{:gutter$} |", "")?;
+            for note in notes.iter() {
+                writeln!(out, "{:gutter$} = {}", "", note)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_label(
+        out: &mut String,
+        source: &str,
+        label: &Label,
+        underline: char,
+        gutter: usize,
+        show_line: bool,
+    ) -> std::fmt::Result {
+        let span = label.location.span;
+
+        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[span.start..]
+            .find('\n')
+            .map_or(source.len(), |i| span.start + i);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+
+        // Keep tabs in the padding so the underline lines up in the terminal
+        let padding: String = source[line_start..span.start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        let underlined = &source[span.start..span.end.min(line_end).max(span.start)];
+        let width = underlined.chars().count().max(1);
+
+        if show_line {
+            writeln!(out, "{:>gutter$} | {}", span.line, line)?;
+        }
+        write!(
+            out,
+            "{:gutter$} | {}{}",
+            "",
+            padding,
+            underline.to_string().repeat(width)
+        )?;
+        if label.message.is_empty() {
+            writeln!(out)
+        } else {
+            writeln!(out, " {}", label.message)
+        }
+    }
+}
+
+impl From<&Error> for Diagnostic {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::UnexpectedToken { token, location } => {
+                Diagnostic::error(format!("unexpected {}", describe_token(token)))
+                    .with_primary(location.clone(), "unexpected token")
+            }
+            Error::MissingColon {
+                key,
+                token,
+                location,
+            } => Diagnostic::error(format!("expected `:` after dictionary key `{}`", key))
+                .with_primary(
+                    location.clone(),
+                    format!("expected `:`, found {}", describe_token(token)),
+                )
+                .with_help("dictionary entries are written as `key: value`"),
+            Error::InvalidKey { token, location } => Diagnostic::error(format!(
+                "expected a dictionary key, found {}",
+                describe_token(token)
+            ))
+            .with_primary(location.clone(), "expected a key")
+            .with_help("keys that are not plain words can be quoted, like `\"1\": value`"),
+            Error::UnknownFunction { name, location } => {
+                Diagnostic::error(format!("unknown function `{}`", name))
+                    .with_primary(location.clone(), "not a registered function")
+                    .with_help("functions must be registered with `Dent::add_function`")
+            }
+            Error::UnexpectedEof { location } => Diagnostic::error("unexpected end of file")
+                .with_primary(location.clone(), "expected more input"),
+            Error::UnexpectedChar { char, location } => {
+                Diagnostic::error(format!("unexpected character `{}`", char))
+                    .with_primary(location.clone(), "not valid in Dent")
+            }
+            Error::UnterminatedString { location } => Diagnostic::error("unterminated string")
+                .with_primary(location.clone(), "string starts here")
+                .with_help("close the string with a matching quote"),
+            Error::InvalidEscape { sequence, location } => Diagnostic::error(format!(
+                "invalid escape sequence `{}`",
+                sequence
+            ))
+            .with_primary(location.clone(), "unknown escape")
+            .with_note(
+                "valid escapes are `\\\"`, `\\\\`, `\\n`, `\\t`, `\\r`, `\\0` and `\\u{...}`",
+            )
+            .with_help("use a triple-quoted string `\"\"\"...\"\"\"` to avoid escaping"),
+            Error::InvalidNumber { literal, location } => {
+                Diagnostic::error(format!("invalid number `{}`", literal))
+                    .with_primary(location.clone(), "not a valid number")
+                    .with_help(
+                        "numbers are written like `42`, `-1.5`, `1e9`, `0xff` or `1_000`; \
+                         quote the value to use it as a string",
+                    )
+            }
+            Error::NumberOverflow { literal, location } => {
+                Diagnostic::error(format!("number `{}` is out of range", literal))
+                    .with_primary(location.clone(), "out of range")
+                    .with_note("integers must fit in 64 bits, between -2^63 and 2^63 - 1")
+            }
+            Error::DuplicateKey {
+                key,
+                first,
+                location,
+            } => Diagnostic::error(format!("duplicate dictionary key `{}`", key))
+                .with_primary(location.clone(), "duplicate key")
+                .with_secondary(first.clone(), "first defined here")
+                .with_help("remove one of the entries"),
+            Error::InvalidQuery { message, location } => {
+                Diagnostic::error("invalid query").with_primary(location.clone(), message.clone())
+            }
+            Error::QueryFailed { message, location } => {
+                Diagnostic::error("query failed").with_primary(location.clone(), message.clone())
+            }
+            Error::Serde { .. } => Diagnostic::error(error.to_string()),
+            Error::InvalidSource {
+                format,
+                message,
+                location,
+            } => Diagnostic::error(format!("invalid {}", format))
+                .with_primary(location.clone(), message.clone()),
+            Error::Conversion { .. } => Diagnostic::error(error.to_string()),
+            Error::InvalidSchema { path, message } if path.is_empty() => {
+                Diagnostic::error(format!("invalid schema: {}", message))
+            }
+            Error::InvalidSchema { path, message } => {
+                Diagnostic::error(format!("invalid schema: {}: {}", path, message))
+            }
+            Error::FunctionFailed {
+                name,
+                message,
+                location,
+            } => Diagnostic::error(format!("call to `{}` failed", name))
+                .with_primary(location.clone(), message.clone()),
+            Error::ImportNotFound {
+                path,
+                tried,
+                location,
+            } => tried.iter().fold(
+                Diagnostic::error(format!("cannot find imported file `{}`", path))
+                    .with_primary(location.clone(), "not found"),
+                |diagnostic, candidate| {
+                    diagnostic.with_note(format!("tried {}", candidate.display()))
+                },
+            ),
+            Error::ImportFailed {
+                path,
+                source,
+                location,
+            } => {
+                // The error itself is the most useful part, with where the
+                // file was imported added to it for every level of imports
+                let diagnostic = Diagnostic::from(source.as_ref());
+                let message = format!("`{}` is imported here", path.display());
+                if diagnostic.primary.is_some() {
+                    diagnostic.with_secondary(location.clone(), message)
+                } else {
+                    diagnostic.with_primary(location.clone(), message)
+                }
+            }
+            Error::ImportCycle { chain, location } => {
+                let chain: Vec<_> = chain.iter().map(|f| f.display().to_string()).collect();
+                Diagnostic::error("import cycle")
+                    .with_primary(
+                        location.clone(),
+                        "imports a file that is already being imported",
+                    )
+                    .with_note(format!(
+                        "the files import each other: {}",
+                        chain.join(" -> ")
+                    ))
+                    .with_help("mark the import with `recursive: true` to read it as `none` here")
+            }
+            Error::Io(kind) => Diagnostic::error(format!("IO error: {}", kind)),
+        }
+    }
+}
+
+impl From<Error> for Diagnostic {
+    fn from(error: Error) -> Self {
+        Diagnostic::from(&error)
+    }
+}
+
+#[cfg(any(feature = "yaml", feature = "toml"))]
+impl From<&crate::ConversionWarning> for Diagnostic {
+    fn from(warning: &crate::ConversionWarning) -> Self {
+        match &warning.location {
+            Some(location) => Diagnostic::warning(warning.to_string())
+                .with_primary(location.clone(), warning.message.clone()),
+            None => Diagnostic::warning(warning.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "schema")]
+impl From<&crate::schema::Violation> for Diagnostic {
+    fn from(violation: &crate::schema::Violation) -> Self {
+        match &violation.location {
+            Some(location) => Diagnostic::error(violation.to_string())
+                .with_primary(location.clone(), violation.message.clone()),
+            None => Diagnostic::error(violation.to_string()),
+        }
+    }
+}
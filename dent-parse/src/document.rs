@@ -0,0 +1,904 @@
+//! Lossless parsing and editing of Dent text.
+//!
+//! `Dent::parse` reduces its input to a `Value`, dropping comments, layout,
+//! quoting style and the spelling of numbers. A `Document` keeps all of them,
+//! so a file can be edited and written back with every region that was not
+//! edited left byte-for-byte identical.
+//!
+//! Functions are not run when parsing a document. Calls such as
+//! `@import "file.dent"` are kept as they are, and read as `Value::None` by
+//! `Node::to_value`.
+//!
+//! # Examples
+//! ```
+//! use dent_parse::{document::Document, Value};
+//!
+//! let mut document = Document::parse(
+//!     "{\n    # The hero\n    name: Mario\n    age: 0x23 # in hex\n}\n",
+//! )
+//! .unwrap();
+//!
+//! let root = document.root_mut();
+//! root.set("name", &Value::Str("Luigi".into()));
+//! root.set("alive", &Value::Bool(true));
+//!
+//! assert_eq!(
+//!     document.to_string(),
+//!     "{\n    # The hero\n    name: Luigi\n    age: 0x23 # in hex\n    alive: true\n}\n"
+//! );
+//! ```
+
+use std::{borrow::Cow, fmt::Display, path::Path, sync::Arc};
+
+use crate::{
+    number::number_value,
+    tokenizer::{Token, Tokenizer},
+    Dict, Emitter, Error, Location, OwnedValue, Result, Span, Value,
+};
+
+/// A parsed Dent text that keeps everything needed to write it back exactly.
+///
+/// Writing a document with `Display` reproduces the text it was parsed from,
+/// with only the edited values changed.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Document {
+    root: Node,
+    trailing: String,
+}
+
+/// A value in a `Document`, along with the comments and whitespace around it.
+///
+/// Everything before a value that is not part of a previous value, such as
+/// the comments on the lines above it, belongs to that value, and is removed
+/// along with it. A comment on the same line after a value also belongs to it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Node {
+    leading: String,
+    kind: NodeKind,
+    trailing: String,
+    /// Indentation of the line the node starts on, and the column it starts
+    /// at in characters, used to lay out values replacing it
+    indent: String,
+    column: usize,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum NodeKind {
+    /// A string, number, boolean or `none`, with its source text
+    Scalar {
+        text: String,
+        value: OwnedValue,
+    },
+    List {
+        items: Vec<Node>,
+        close: String,
+    },
+    Dict {
+        entries: Vec<Entry>,
+        close: String,
+    },
+    /// A function call, with `head` holding the source text from the `@` to
+    /// the end of the function name
+    Call {
+        head: String,
+        argument: Box<Node>,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Entry {
+    leading: String,
+    key: String,
+    key_text: String,
+    /// Source text from the end of the key to the end of the colon
+    colon: String,
+    value: Node,
+}
+
+impl Document {
+    /// Parses a Dent string into a document.
+    ///
+    /// Fails on the same syntax errors as `Dent::parse`, except for unknown
+    /// functions, as functions are not run.
+    pub fn parse(input: &str) -> Result<Document> {
+        let mut parser = DocumentParser::new(input)?;
+
+        let root = if parser.token == Token::Eof {
+            Node {
+                leading: String::new(),
+                kind: NodeKind::Scalar {
+                    text: String::new(),
+                    value: Value::None,
+                },
+                trailing: String::new(),
+                indent: String::new(),
+                column: 0,
+            }
+        } else {
+            parser.node()?
+        };
+
+        Ok(Document {
+            root,
+            trailing: input[parser.prev_end..].to_string(),
+        })
+    }
+
+    /// Parses a Dent file into a document.
+    ///
+    /// Errors encountered while parsing the file carry its path in their
+    /// `Location`.
+    pub fn parse_file<P: AsRef<Path>>(path: P) -> Result<Document> {
+        let input = std::fs::read_to_string(path.as_ref())?;
+        let file: Arc<Path> = Arc::from(path.as_ref());
+        Document::parse(&input).map_err(|e| e.with_file(&file))
+    }
+
+    /// Returns the top-level value of the document.
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    /// Returns the top-level value of the document, for editing.
+    pub fn root_mut(&mut self) -> &mut Node {
+        &mut self.root
+    }
+
+    /// Returns the value the document represents.
+    pub fn to_value(&self) -> OwnedValue {
+        self.root.to_value()
+    }
+
+    /// Writes the document in a canonical layout, keeping its comments.
+    ///
+    /// Lists and dictionaries are written on a single line if they contain no
+    /// comments and fit in the width of the emitter, and otherwise with every
+    /// element or entry on its own line, indented with the indentation of the
+    /// emitter. Comments go on their own lines, except for comments after a
+    /// value on the same line, and runs of blank lines collapse into one.
+    /// Strings and numbers are kept as they are written.
+    ///
+    /// # Examples
+    /// ```
+    /// use dent_parse::{document::Document, Emitter};
+    ///
+    /// let document = Document::parse("{ b: [ 1 2 ]\n  # first\n       a:   1 }").unwrap();
+    ///
+    /// assert_eq!(
+    ///     document.format(&Emitter::new().with_sort_keys(true)),
+    ///     "{\n    # first\n    a: 1\n    b: [ 1 2 ]\n}\n"
+    /// );
+    /// ```
+    pub fn format(&self, emitter: &Emitter) -> String {
+        let mut formatter = Formatter {
+            emitter,
+            out: String::new(),
+            blank: false,
+        };
+
+        formatter.comments(&self.root.leading, 0, false);
+        if !matches!(&self.root.kind, NodeKind::Scalar { text, .. } if text.is_empty()) {
+            formatter.line(0);
+            formatter.node(&self.root, 0);
+        }
+
+        let trailing = format!("{}{}", self.root.trailing, self.trailing);
+        if is_trivia(&trailing) {
+            formatter.trailing(&trailing, 0);
+        } else {
+            formatter.line(0);
+            formatter.out.push_str(trailing.trim());
+        }
+
+        let mut out = formatter.out;
+        if emitter.trailing_newline && !out.is_empty() {
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}{}",
+            self.root.leading, self.root, self.root.trailing, self.trailing
+        )
+    }
+}
+
+impl Node {
+    /// Returns the value the node represents. Function calls are read as
+    /// `Value::None`.
+    pub fn to_value(&self) -> OwnedValue {
+        match &self.kind {
+            NodeKind::Scalar { value, .. } => value.clone(),
+            NodeKind::List { items, .. } => Value::List(items.iter().map(Node::to_value).collect()),
+            NodeKind::Dict { entries, .. } => {
+                let mut dict = Dict::new();
+                for entry in entries {
+                    dict.insert(Cow::Owned(entry.key.clone()), entry.value.to_value());
+                }
+                Value::Dict(dict)
+            }
+            NodeKind::Call { .. } => Value::None,
+        }
+    }
+
+    /// Returns true if the node is a list
+    pub fn is_list(&self) -> bool {
+        matches!(self.kind, NodeKind::List { .. })
+    }
+
+    /// Returns true if the node is a dictionary
+    pub fn is_dict(&self) -> bool {
+        matches!(self.kind, NodeKind::Dict { .. })
+    }
+
+    /// Returns true if the node is a function call
+    pub fn is_call(&self) -> bool {
+        matches!(self.kind, NodeKind::Call { .. })
+    }
+
+    /// Returns the number of elements or entries, if the node is a list or
+    /// dictionary
+    pub fn len(&self) -> Option<usize> {
+        match &self.kind {
+            NodeKind::List { items, .. } => Some(items.len()),
+            NodeKind::Dict { entries, .. } => Some(entries.len()),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the node is an empty list or dictionary
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    /// Returns the keys of a dictionary in order, or nothing for other nodes.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        let entries: &[Entry] = match &self.kind {
+            NodeKind::Dict { entries, .. } => entries,
+            _ => &[],
+        };
+        entries.iter().map(|e| e.key.as_str())
+    }
+
+    /// Returns the value of a dictionary key, if the node is a dictionary
+    /// containing it.
+    ///
+    /// If the key appears more than once, the last value is returned, as it is
+    /// the one `Dent::parse` keeps by default.
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        match &self.kind {
+            NodeKind::Dict { entries, .. } => entries
+                .iter()
+                .rev()
+                .find(|e| e.key == key)
+                .map(|e| &e.value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a dictionary key for editing, if the node is a
+    /// dictionary containing it.
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Node> {
+        match &mut self.kind {
+            NodeKind::Dict { entries, .. } => entries
+                .iter_mut()
+                .rev()
+                .find(|e| e.key == key)
+                .map(|e| &mut e.value),
+            _ => None,
+        }
+    }
+
+    /// Returns a list element, if the node is a list and the index is in
+    /// bounds.
+    pub fn get_index(&self, index: usize) -> Option<&Node> {
+        match &self.kind {
+            NodeKind::List { items, .. } => items.get(index),
+            _ => None,
+        }
+    }
+
+    /// Returns a list element for editing, if the node is a list and the index
+    /// is in bounds.
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut Node> {
+        match &mut self.kind {
+            NodeKind::List { items, .. } => items.get_mut(index),
+            _ => None,
+        }
+    }
+
+    /// Replaces the node with a new value, keeping the comments around it.
+    ///
+    /// The value is written by `Emitter::new`, indented to match the line the
+    /// node is on.
+    pub fn replace(&mut self, value: &Value) {
+        let node = Node::new(value, String::new(), &self.indent, self.column);
+        self.kind = node.kind;
+    }
+
+    /// Sets the value of a dictionary key.
+    ///
+    /// An existing value is replaced, keeping the comments around it. A new
+    /// key is added at the end of the dictionary, laid out like the entry
+    /// before it.
+    ///
+    /// # Panics
+    /// Panics if the node is not a dictionary.
+    pub fn set(&mut self, key: &str, value: &Value) {
+        if let Some(node) = self.get_mut(key) {
+            node.replace(value);
+            return;
+        }
+
+        let (entries, close) = match &mut self.kind {
+            NodeKind::Dict { entries, close } => (entries, close),
+            _ => panic!("Cannot set key of non-dict value"),
+        };
+
+        let leading = match entries.last() {
+            Some(entry) => separator(&entry.leading),
+            None => {
+                if close.is_empty() {
+                    *close = " ".to_string();
+                }
+                " ".to_string()
+            }
+        };
+        let indent = line_indent(&leading).to_string();
+
+        let mut key_text = String::new();
+        Emitter::compact().write_str(&mut key_text, key);
+        let column = indent.chars().count() + key_text.chars().count() + 2;
+
+        entries.push(Entry {
+            leading,
+            key: key.to_string(),
+            key_text,
+            colon: ":".to_string(),
+            value: Node::new(value, " ".to_string(), &indent, column),
+        });
+    }
+
+    /// Removes a dictionary key, along with the comments before it, returning
+    /// whether it was present. If the key appears more than once, all of its
+    /// entries are removed.
+    ///
+    /// # Panics
+    /// Panics if the node is not a dictionary.
+    pub fn remove(&mut self, key: &str) -> bool {
+        match &mut self.kind {
+            NodeKind::Dict { entries, .. } => {
+                let len = entries.len();
+                entries.retain(|e| e.key != key);
+                entries.len() != len
+            }
+            _ => panic!("Cannot remove key of non-dict value"),
+        }
+    }
+
+    /// Inserts an element into a list at an index, laid out like the element
+    /// it is inserted before, or the last element.
+    ///
+    /// # Panics
+    /// Panics if the node is not a list, or the index is out of bounds.
+    pub fn insert(&mut self, index: usize, value: &Value) {
+        let (items, close) = match &mut self.kind {
+            NodeKind::List { items, close } => (items, close),
+            _ => panic!("Cannot insert into non-list value"),
+        };
+
+        let leading = match items.get(index).or(items.last()) {
+            Some(item) => separator(&item.leading),
+            None => {
+                if close.is_empty() {
+                    *close = " ".to_string();
+                }
+                " ".to_string()
+            }
+        };
+        let indent = line_indent(&leading).to_string();
+
+        let column = indent.chars().count();
+        items.insert(index, Node::new(value, leading, &indent, column));
+    }
+
+    /// Appends an element to a list.
+    ///
+    /// # Panics
+    /// Panics if the node is not a list.
+    pub fn push(&mut self, value: &Value) {
+        let len = self.len().unwrap_or(0);
+        self.insert(len, value);
+    }
+
+    /// Removes a list element, along with the comments before it, returning
+    /// it if the index was in bounds.
+    ///
+    /// # Panics
+    /// Panics if the node is not a list.
+    pub fn remove_index(&mut self, index: usize) -> Option<Node> {
+        match &mut self.kind {
+            NodeKind::List { items, .. } if index < items.len() => Some(items.remove(index)),
+            NodeKind::List { .. } => None,
+            _ => panic!("Cannot remove element of non-list value"),
+        }
+    }
+
+    /// Creates a node for a new value, written by `Emitter::new` as if it
+    /// started at `column`, with every line after the first indented by
+    /// `indent`.
+    fn new(value: &Value, leading: String, indent: &str, column: usize) -> Node {
+        let text = Emitter::new().emit_nested(value, indent, column);
+
+        // Emitted text always parses
+        let mut parser = DocumentParser::new(&text).unwrap();
+        let mut node = parser.node().unwrap();
+        node.leading = leading;
+        node.indent = indent.to_string();
+        node.column = column;
+        node
+    }
+}
+
+/// Writes the source text of the node, without the comments and whitespace
+/// around it.
+impl Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            NodeKind::Scalar { text, .. } => write!(f, "{}", text),
+            NodeKind::List { items, close } => {
+                write!(f, "[")?;
+                for item in items {
+                    write!(f, "{}{}{}", item.leading, item, item.trailing)?;
+                }
+                write!(f, "{}]", close)
+            }
+            NodeKind::Dict { entries, close } => {
+                write!(f, "{{")?;
+                for entry in entries {
+                    write!(
+                        f,
+                        "{}{}{}{}{}{}",
+                        entry.leading,
+                        entry.key_text,
+                        entry.colon,
+                        entry.value.leading,
+                        entry.value,
+                        entry.value.trailing
+                    )?;
+                }
+                write!(f, "{}}}", close)
+            }
+            NodeKind::Call { head, argument } => {
+                write!(
+                    f,
+                    "{}{}{}{}",
+                    head, argument.leading, argument, argument.trailing
+                )
+            }
+        }
+    }
+}
+
+/// A comment or a blank line between values.
+enum Trivia<'t> {
+    Comment(&'t str),
+    Blank,
+}
+
+/// Splits the text between two values into comments and blank lines.
+fn trivia(text: &str) -> Vec<Trivia<'_>> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let mut result = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            result.push(Trivia::Comment(line));
+        } else if line.is_empty() && i != 0 && i != lines.len() - 1 {
+            result.push(Trivia::Blank);
+        }
+    }
+    result
+}
+
+/// Returns true if some text contains only whitespace and comments.
+fn is_trivia(text: &str) -> bool {
+    text.lines().all(|l| {
+        let l = l.trim();
+        l.is_empty() || l.starts_with('#')
+    })
+}
+
+fn has_comments(text: &str) -> bool {
+    text.contains('#')
+}
+
+/// Lays out a document for `Document::format`.
+struct Formatter<'e> {
+    emitter: &'e Emitter,
+    out: String,
+    /// Whether a blank line goes before the next line
+    blank: bool,
+}
+
+impl<'e> Formatter<'e> {
+    /// Starts a new line, indented to a level.
+    fn line(&mut self, level: usize) {
+        if !self.out.is_empty() {
+            if self.blank {
+                self.out.push('\n');
+            }
+            self.out.push('\n');
+        }
+        self.blank = false;
+        for _ in 0..level {
+            self.out.push_str(&self.emitter.indent);
+        }
+    }
+
+    /// Writes the comments in some text on their own lines. Blank lines are
+    /// kept once something has been written at this level, or if
+    /// `blank_lines` is set.
+    fn comments(&mut self, text: &str, level: usize, mut blank_lines: bool) {
+        for trivia in trivia(text) {
+            match trivia {
+                Trivia::Comment(comment) => {
+                    self.line(level);
+                    self.out.push_str(comment);
+                    blank_lines = true;
+                }
+                Trivia::Blank => self.blank = blank_lines,
+            }
+        }
+    }
+
+    /// Writes the comments after a value, keeping a comment on the same line
+    /// as the value there.
+    fn trailing(&mut self, text: &str, level: usize) {
+        let (line, rest) = text.split_at(text.find('\n').unwrap_or(text.len()));
+        if has_comments(line) {
+            self.out.push(' ');
+            self.out.push_str(line.trim());
+        }
+        self.comments(rest, level, true);
+        self.blank = false;
+    }
+
+    fn node(&mut self, node: &Node, level: usize) {
+        match &node.kind {
+            NodeKind::Scalar { text, .. } => self.out.push_str(text),
+            NodeKind::Call { head, argument } => {
+                self.out.push('@');
+                self.out.push_str(head[1..].trim());
+                self.out.push(' ');
+                for trivia in trivia(&argument.leading) {
+                    if let Trivia::Comment(comment) = trivia {
+                        self.out.push_str(comment);
+                        self.line(level);
+                    }
+                }
+                self.node(argument, level);
+            }
+            NodeKind::List { .. } | NodeKind::Dict { .. } => {
+                let column = self.out[self.out.rfind('\n').map_or(0, |i| i + 1)..]
+                    .chars()
+                    .count();
+                match self.flat(node) {
+                    Some(flat) if column + flat.chars().count() <= self.emitter.width => {
+                        self.out.push_str(&flat)
+                    }
+                    _ => self.multiline(node, level),
+                }
+            }
+        }
+    }
+
+    fn multiline(&mut self, node: &Node, level: usize) {
+        let (close_bracket, close, empty) = match &node.kind {
+            NodeKind::List { items, close } => {
+                self.out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    self.comments(&item.leading, level + 1, i > 0);
+                    self.line(level + 1);
+                    self.node(item, level + 1);
+                    self.trailing(&item.trailing, level + 1);
+                }
+                (']', close, items.is_empty())
+            }
+            NodeKind::Dict { entries, close } => {
+                self.out.push('{');
+                for (i, entry) in self.entries(entries).into_iter().enumerate() {
+                    self.comments(&entry.leading, level + 1, i > 0);
+                    self.comments(&entry.colon, level + 1, false);
+                    self.comments(&entry.value.leading, level + 1, false);
+                    self.line(level + 1);
+                    self.out.push_str(&entry.key_text);
+                    self.out.push_str(": ");
+                    self.node(&entry.value, level + 1);
+                    self.trailing(&entry.value.trailing, level + 1);
+                }
+                ('}', close, entries.is_empty())
+            }
+            _ => unreachable!(),
+        };
+
+        // Blank lines in an empty list or dictionary are dropped, as there is
+        // nothing to separate
+        self.comments(close, level + 1, !empty);
+        self.blank = false;
+        self.line(level);
+        self.out.push(close_bracket);
+    }
+
+    /// Returns the entries of a dictionary in the order they are written in.
+    fn entries<'n>(&self, entries: &'n [Entry]) -> Vec<&'n Entry> {
+        let mut entries: Vec<&Entry> = entries.iter().collect();
+        if self.emitter.sort_keys {
+            entries.sort_by(|a, b| a.key.cmp(&b.key));
+        }
+        entries
+    }
+
+    /// Returns a node written on a single line, unless it contains comments or
+    /// line breaks.
+    fn flat(&self, node: &Node) -> Option<String> {
+        match &node.kind {
+            NodeKind::Scalar { text, .. } if text.contains('\n') => None,
+            NodeKind::Scalar { text, .. } => Some(text.clone()),
+            NodeKind::Call { head, argument } => {
+                if has_comments(&argument.leading) {
+                    return None;
+                }
+                Some(format!("@{} {}", head[1..].trim(), self.flat(argument)?))
+            }
+            NodeKind::List { items, close } => {
+                if has_comments(close) {
+                    return None;
+                }
+                let mut out = String::from("[");
+                for item in items {
+                    if has_comments(&item.leading) || has_comments(&item.trailing) {
+                        return None;
+                    }
+                    out.push(' ');
+                    out.push_str(&self.flat(item)?);
+                }
+                out.push_str(if items.is_empty() { "]" } else { " ]" });
+                Some(out)
+            }
+            NodeKind::Dict { entries, close } => {
+                if has_comments(close) {
+                    return None;
+                }
+                let mut out = String::from("{");
+                for entry in self.entries(entries) {
+                    if [
+                        &entry.leading,
+                        &entry.colon,
+                        &entry.value.leading,
+                        &entry.value.trailing,
+                    ]
+                    .iter()
+                    .any(|t| has_comments(t))
+                    {
+                        return None;
+                    }
+                    out.push(' ');
+                    out.push_str(&entry.key_text);
+                    out.push_str(": ");
+                    out.push_str(&self.flat(&entry.value)?);
+                }
+                out.push_str(if entries.is_empty() { "}" } else { " }" });
+                Some(out)
+            }
+        }
+    }
+}
+
+/// Returns the whitespace to put before a new element or entry, based on the
+/// whitespace before an existing one: a line break and the same indentation
+/// if it is on its own line, and a space otherwise.
+fn separator(leading: &str) -> String {
+    match leading.rfind('\n') {
+        Some(_) => format!("\n{}", line_indent(leading)),
+        None => " ".to_string(),
+    }
+}
+
+/// Returns the indentation of the last line of some text.
+fn line_indent(text: &str) -> &str {
+    let line = &text[text.rfind('\n').map_or(0, |i| i + 1)..];
+    let end = line
+        .find(|c: char| c != ' ' && c != '\t')
+        .unwrap_or(line.len());
+    &line[..end]
+}
+
+struct DocumentParser<'s> {
+    input: &'s str,
+    tokenizer: Tokenizer<'s>,
+    token: Token<'s>,
+    span: Span,
+    /// End of the last token that was not a comment
+    prev_end: usize,
+}
+
+impl<'s> DocumentParser<'s> {
+    fn new(input: &'s str) -> Result<Self> {
+        let mut parser = DocumentParser {
+            input,
+            tokenizer: Tokenizer::new(input),
+            token: Token::Eof,
+            span: Span::default(),
+            prev_end: 0,
+        };
+        parser.read()?;
+        Ok(parser)
+    }
+
+    /// Reads the next token, skipping comments.
+    fn read(&mut self) -> Result<()> {
+        loop {
+            self.token = self.tokenizer.next()?;
+            self.span = self.tokenizer.span();
+            if self.token != Token::Comment {
+                return Ok(());
+            }
+        }
+    }
+
+    fn next(&mut self) -> Result<()> {
+        self.prev_end = self.span.end;
+        self.read()
+    }
+
+    /// Returns the comments and whitespace before the current token.
+    fn leading(&self) -> String {
+        self.input[self.prev_end..self.span.start].to_string()
+    }
+
+    fn text(&self) -> &'s str {
+        &self.input[self.span.start..self.span.end]
+    }
+
+    /// Takes a comment on the same line as the previous token, which belongs
+    /// to the node that token ends.
+    fn trailing(&mut self) -> String {
+        let rest = &self.input[self.prev_end..self.span.start];
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        if line.contains('#') {
+            self.prev_end += line.len();
+            line.to_string()
+        } else {
+            String::new()
+        }
+    }
+
+    fn unexpected_token(&self) -> Error {
+        match self.token {
+            Token::Eof => Error::UnexpectedEof {
+                location: Location::new(self.span),
+            },
+            _ => Error::UnexpectedToken {
+                token: self.token.type_name(),
+                location: Location::new(self.span),
+            },
+        }
+    }
+
+    fn node(&mut self) -> Result<Node> {
+        let leading = self.leading();
+        let before = &self.input[..self.span.start];
+        let indent = line_indent(before).to_string();
+        let column = before[before.rfind('\n').map_or(0, |i| i + 1)..]
+            .chars()
+            .count();
+
+        let kind = match &self.token {
+            Token::String(s) => {
+                let value = Value::Str(Cow::Owned(s.to_string()));
+                self.scalar(value)?
+            }
+            Token::Number(n) => {
+                let value = number_value(n, Location::new(self.span))?;
+                self.scalar(value)?
+            }
+            Token::Bool(b) => {
+                let value = Value::Bool(*b);
+                self.scalar(value)?
+            }
+            Token::None => self.scalar(Value::None)?,
+            Token::OpenBracket => {
+                self.next()?;
+                let mut items = Vec::new();
+                while self.token != Token::CloseBracket {
+                    let mut item = self.node()?;
+                    item.trailing = self.trailing();
+                    items.push(item);
+                }
+                let close = self.leading();
+                self.next()?;
+                NodeKind::List { items, close }
+            }
+            Token::OpenBrace => {
+                self.next()?;
+                let mut entries = Vec::new();
+                while self.token != Token::CloseBrace {
+                    entries.push(self.entry()?);
+                }
+                let close = self.leading();
+                self.next()?;
+                NodeKind::Dict { entries, close }
+            }
+            Token::At => {
+                let start = self.span.start;
+                self.next()?;
+                if !matches!(self.token, Token::String(_)) {
+                    return Err(self.unexpected_token());
+                }
+                let head = self.input[start..self.span.end].to_string();
+                self.next()?;
+                let argument = Box::new(self.node()?);
+                NodeKind::Call { head, argument }
+            }
+            _ => return Err(self.unexpected_token()),
+        };
+
+        Ok(Node {
+            leading,
+            kind,
+            trailing: String::new(),
+            indent,
+            column,
+        })
+    }
+
+    fn scalar(&mut self, value: OwnedValue) -> Result<NodeKind> {
+        let text = self.text().to_string();
+        self.next()?;
+        Ok(NodeKind::Scalar { text, value })
+    }
+
+    fn entry(&mut self) -> Result<Entry> {
+        let leading = self.leading();
+        let key = match self.token.key() {
+            Some(key) => key.to_string(),
+            None if self.token == Token::Eof => return Err(self.unexpected_token()),
+            None => {
+                return Err(Error::InvalidKey {
+                    token: self.token.type_name(),
+                    location: Location::new(self.span),
+                })
+            }
+        };
+        let key_text = self.text().to_string();
+        let key_end = self.span.end;
+        self.next()?;
+
+        if self.token != Token::Colon {
+            return Err(Error::MissingColon {
+                key,
+                token: self.token.type_name(),
+                location: Location::new(self.span),
+            });
+        }
+        let colon = self.input[key_end..self.span.end].to_string();
+        self.next()?;
+
+        let mut value = self.node()?;
+        value.trailing = self.trailing();
+
+        Ok(Entry {
+            leading,
+            key,
+            key_text,
+            colon,
+            value,
+        })
+    }
+}
@@ -0,0 +1,279 @@
+use std::fmt::Write;
+
+use crate::Value;
+
+/// Words the tokenizer reads as something other than a string.
+const KEYWORDS: &[&str] = &[
+    "true", "false", "none", "inf", "+inf", "-inf", "nan", "+nan", "-nan",
+];
+
+/// Which strings an `Emitter` quotes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Quoting {
+    /// Quote only the strings that would not be read back as the same string
+    /// as a bare word, such as `hello world`, `true` or `42`.
+    #[default]
+    Minimal,
+    /// Quote every string and dictionary key.
+    Always,
+}
+
+/// Writes `Value`s as Dent text.
+///
+/// The output of an emitter always parses back into the value it was made
+/// from: strings are quoted and escaped where needed, floats always have a
+/// fraction or an exponent, and `Value::None` is written as `none`. The only
+/// exception is that a float that is NaN does not compare equal to itself.
+///
+/// By default, lists and dictionaries are written on a single line if they fit
+/// in 80 columns, and otherwise with every element or entry on its own line,
+/// indented by four spaces.
+///
+/// # Examples
+/// ```
+/// use dent_parse::{Dent, Emitter};
+///
+/// let value = Dent::default()
+///     .parse("{ name: \"Super Mario\" skills: [ jumps grows ] age: 35 }")
+///     .unwrap();
+///
+/// assert_eq!(
+///     Emitter::new().with_width(30).emit(&value),
+///     "{\n    name: \"Super Mario\"\n    skills: [ jumps grows ]\n    age: 35\n}\n"
+/// );
+/// assert_eq!(
+///     Emitter::compact().emit(&value),
+///     "{ name: \"Super Mario\" skills: [ jumps grows ] age: 35 }"
+/// );
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Emitter {
+    pub(crate) indent: String,
+    pub(crate) width: usize,
+    compact: bool,
+    pub(crate) trailing_newline: bool,
+    quoting: Quoting,
+    pub(crate) sort_keys: bool,
+    /// Text written at the start of every line after the first
+    margin: String,
+}
+
+impl Default for Emitter {
+    fn default() -> Self {
+        Emitter::new()
+    }
+}
+
+impl Emitter {
+    /// Creates an emitter for human-readable output.
+    pub fn new() -> Emitter {
+        Emitter {
+            indent: "    ".to_string(),
+            width: 80,
+            compact: false,
+            trailing_newline: true,
+            quoting: Quoting::Minimal,
+            sort_keys: false,
+            margin: String::new(),
+        }
+    }
+
+    /// Creates an emitter for machine output, which writes the whole value on
+    /// a single line with no trailing line break.
+    pub fn compact() -> Emitter {
+        Emitter {
+            compact: true,
+            trailing_newline: false,
+            ..Emitter::new()
+        }
+    }
+
+    /// Sets the string lines are indented with for each level of nesting.
+    pub fn with_indent(mut self, indent: &str) -> Self {
+        self.indent = indent.to_string();
+        self
+    }
+
+    /// Sets the line width lists and dictionaries have to fit in to be written
+    /// on a single line. With a width of 0, they are always split over
+    /// multiple lines, unless they are empty.
+    ///
+    /// Has no effect on compact emitters.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Sets whether the output ends with a line break.
+    pub fn with_trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.trailing_newline = trailing_newline;
+        self
+    }
+
+    /// Sets whether dictionary entries are written sorted by key, rather than
+    /// in their original order.
+    pub fn with_sort_keys(mut self, sort_keys: bool) -> Self {
+        self.sort_keys = sort_keys;
+        self
+    }
+
+    /// Sets which strings are quoted.
+    pub fn with_quoting(mut self, quoting: Quoting) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// Writes a value as Dent text.
+    pub fn emit(&self, value: &Value) -> String {
+        let mut out = String::new();
+        self.write_value(&mut out, value, 0);
+        if self.trailing_newline {
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Writes a value that is nested in other text, starting at `column` on
+    /// the current line, with every following line starting with `margin`.
+    pub(crate) fn emit_nested(&self, value: &Value, margin: &str, column: usize) -> String {
+        let emitter = Emitter {
+            margin: margin.to_string(),
+            ..self.clone()
+        };
+        let mut out = " ".repeat(column);
+        emitter.write_value(&mut out, value, 0);
+        out.split_off(column)
+    }
+
+    fn write_value(&self, out: &mut String, value: &Value, level: usize) {
+        match value {
+            Value::None => out.push_str("none"),
+            Value::Str(s) => self.write_str(out, s),
+            Value::Int(i) => {
+                let _ = write!(out, "{}", i);
+            }
+            Value::Float(f) => write_float(out, *f),
+            Value::Bool(b) => {
+                let _ = write!(out, "{}", b);
+            }
+            Value::List(l) if l.is_empty() => out.push_str("[]"),
+            Value::Dict(d) if d.is_empty() => out.push_str("{}"),
+            Value::List(l) => {
+                let multiline = self.is_multiline(out, value);
+                out.push('[');
+                for v in l {
+                    self.separate(out, multiline, level + 1);
+                    self.write_value(out, v, level + 1);
+                }
+                self.separate(out, multiline, level);
+                out.push(']');
+            }
+            Value::Dict(d) => {
+                let multiline = self.is_multiline(out, value);
+                out.push('{');
+                let mut entries: Vec<_> = d.iter().collect();
+                if self.sort_keys {
+                    entries.sort_by(|a, b| a.0.cmp(b.0));
+                }
+                for (k, v) in entries {
+                    self.separate(out, multiline, level + 1);
+                    self.write_str(out, k);
+                    out.push_str(": ");
+                    self.write_value(out, v, level + 1);
+                }
+                self.separate(out, multiline, level);
+                out.push('}');
+            }
+        }
+    }
+
+    /// Returns true if a list or dictionary has to be split over multiple
+    /// lines, because it does not fit on the rest of the current line.
+    fn is_multiline(&self, out: &str, value: &Value) -> bool {
+        if self.compact {
+            return false;
+        }
+
+        let line = &out[out.rfind('\n').map_or(0, |i| i + 1)..];
+        let mut flat = String::new();
+        Emitter {
+            compact: true,
+            ..self.clone()
+        }
+        .write_value(&mut flat, value, 0);
+
+        line.chars().count() + flat.chars().count() > self.width
+    }
+
+    fn separate(&self, out: &mut String, multiline: bool, level: usize) {
+        if multiline {
+            out.push('\n');
+            out.push_str(&self.margin);
+            for _ in 0..level {
+                out.push_str(&self.indent);
+            }
+        } else {
+            out.push(' ');
+        }
+    }
+
+    /// Writes a string, quoting and escaping it if needed.
+    pub(crate) fn write_str(&self, out: &mut String, s: &str) {
+        if self.quoting == Quoting::Minimal && !needs_quotes(s) {
+            out.push_str(s);
+            return;
+        }
+
+        out.push('"');
+        for c in s.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\t' => out.push_str("\\t"),
+                '\r' => out.push_str("\\r"),
+                '\0' => out.push_str("\\0"),
+                c if c.is_control() => {
+                    let _ = write!(out, "\\u{{{:x}}}", c as u32);
+                }
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+}
+
+/// Returns true if a string has to be quoted to be read back as the same
+/// string, following the rules for bare words in `Tokenizer::next`.
+fn needs_quotes(s: &str) -> bool {
+    let mut chars = s.chars();
+    let first = match chars.next() {
+        Some(c) => c,
+        None => return true,
+    };
+
+    if KEYWORDS.contains(&s) || first.is_ascii_digit() {
+        return true;
+    }
+
+    let mut rest = chars.clone();
+    if (first == '-' || first == '+') && rest.next().is_some_and(|c| c.is_ascii_digit()) {
+        return true;
+    }
+
+    let bare_start =
+        first.is_alphabetic() || matches!(first, '_' | '-' | '+' | '.' | ',' | '/' | '\\');
+    !bare_start || !chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Writes a float so it is read back as a float, even if it has no fraction.
+fn write_float(out: &mut String, f: f64) {
+    if f.is_nan() {
+        out.push_str("nan");
+    } else if f.is_infinite() {
+        out.push_str(if f > 0.0 { "inf" } else { "-inf" });
+    } else {
+        // `Debug` always includes a `.` or an exponent
+        let _ = write!(out, "{:?}", f);
+    }
+}
@@ -1,33 +1,283 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{tokenizer::describe_token, Location};
+
 /// Error type returned by Dent.
 ///
 /// This type is used for all errors returned by Dent, whether they are
 /// parsing errors, IO errors or otherwise.
+///
+/// Errors produced while parsing carry the `Location` they occurred at, which
+/// is rendered as `file:line:column` by `Display`.
 #[derive(Clone, PartialEq, Debug)]
 pub enum Error {
-    UnexpectedToken(String),
-    UnknownFunction(String),
-    UnexpectedEof,
-    UnexpectedChar(char),
+    UnexpectedToken {
+        token: String,
+        location: Location,
+    },
+    MissingColon {
+        key: String,
+        token: String,
+        location: Location,
+    },
+    InvalidKey {
+        token: String,
+        location: Location,
+    },
+    UnknownFunction {
+        name: String,
+        location: Location,
+    },
+    UnexpectedEof {
+        location: Location,
+    },
+    UnexpectedChar {
+        char: char,
+        location: Location,
+    },
+    UnterminatedString {
+        location: Location,
+    },
+    InvalidEscape {
+        sequence: String,
+        location: Location,
+    },
+    InvalidNumber {
+        literal: String,
+        location: Location,
+    },
+    NumberOverflow {
+        literal: String,
+        location: Location,
+    },
+    DuplicateKey {
+        key: String,
+        first: Location,
+        location: Location,
+    },
+    InvalidQuery {
+        message: String,
+        location: Location,
+    },
+    QueryFailed {
+        message: String,
+        location: Location,
+    },
+    Serde {
+        path: String,
+        message: String,
+    },
+    InvalidSource {
+        format: String,
+        message: String,
+        location: Location,
+    },
+    Conversion {
+        path: String,
+        message: String,
+    },
+    InvalidSchema {
+        path: String,
+        message: String,
+    },
+    FunctionFailed {
+        name: String,
+        message: String,
+        location: Location,
+    },
+    ImportNotFound {
+        /// The path as it was written in the `@import`
+        path: String,
+        /// Every path the file was looked for at, in order
+        tried: Vec<PathBuf>,
+        location: Location,
+    },
+    /// An imported file could not be read or parsed. The location is that of
+    /// the `@import`.
+    ImportFailed {
+        path: PathBuf,
+        /// The error in the imported file, which is another `ImportFailed` if
+        /// it is in a file imported from there, and so on
+        source: Box<Error>,
+        location: Location,
+    },
+    /// A file imports itself, directly or through other files, without the
+    /// import being marked as recursive. The location is that of the
+    /// `@import` that closes the cycle.
+    ImportCycle {
+        /// The canonical paths of the files in the cycle, starting and ending
+        /// with the same file
+        chain: Vec<PathBuf>,
+        location: Location,
+    },
     Io(std::io::ErrorKind),
 }
 
+impl Error {
+    /// Returns the location in the source the error occurred at, if it has one.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::UnexpectedToken { location, .. }
+            | Error::MissingColon { location, .. }
+            | Error::InvalidKey { location, .. }
+            | Error::UnknownFunction { location, .. }
+            | Error::UnexpectedEof { location }
+            | Error::UnexpectedChar { location, .. }
+            | Error::UnterminatedString { location }
+            | Error::InvalidEscape { location, .. }
+            | Error::InvalidNumber { location, .. }
+            | Error::NumberOverflow { location, .. }
+            | Error::DuplicateKey { location, .. }
+            | Error::InvalidQuery { location, .. }
+            | Error::QueryFailed { location, .. }
+            | Error::InvalidSource { location, .. }
+            | Error::FunctionFailed { location, .. }
+            | Error::ImportNotFound { location, .. }
+            | Error::ImportFailed { location, .. }
+            | Error::ImportCycle { location, .. } => Some(location),
+            Error::Serde { .. }
+            | Error::Conversion { .. }
+            | Error::InvalidSchema { .. }
+            | Error::Io(_) => None,
+        }
+    }
+
+    fn location_mut(&mut self) -> Option<&mut Location> {
+        match self {
+            Error::UnexpectedToken { location, .. }
+            | Error::MissingColon { location, .. }
+            | Error::InvalidKey { location, .. }
+            | Error::UnknownFunction { location, .. }
+            | Error::UnexpectedEof { location }
+            | Error::UnexpectedChar { location, .. }
+            | Error::UnterminatedString { location }
+            | Error::InvalidEscape { location, .. }
+            | Error::InvalidNumber { location, .. }
+            | Error::NumberOverflow { location, .. }
+            | Error::DuplicateKey { location, .. }
+            | Error::InvalidQuery { location, .. }
+            | Error::QueryFailed { location, .. }
+            | Error::InvalidSource { location, .. }
+            | Error::FunctionFailed { location, .. }
+            | Error::ImportNotFound { location, .. }
+            | Error::ImportFailed { location, .. }
+            | Error::ImportCycle { location, .. } => Some(location),
+            Error::Serde { .. }
+            | Error::Conversion { .. }
+            | Error::InvalidSchema { .. }
+            | Error::Io(_) => None,
+        }
+    }
+
+    /// Attaches a file path to the error's location, unless it already has one.
+    pub(crate) fn with_file(mut self, path: &Arc<Path>) -> Self {
+        if let Error::DuplicateKey { first, .. } = &mut self {
+            if first.file.is_none() {
+                first.file = Some(path.clone());
+            }
+        }
+        if let Some(location) = self.location_mut() {
+            if location.file.is_none() {
+                location.file = Some(path.clone());
+            }
+        }
+        self
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
         Error::Io(e.kind())
     }
 }
 
-impl ToString for Error {
-    fn to_string(&self) -> String {
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(location) = self.location() {
+            write!(f, "{}: ", location)?;
+        }
         match self {
-            Error::UnexpectedToken(token) => format!("Unexpected token: {}", token),
-            Error::UnknownFunction(name) => format!("Unknown function: {}", name),
-            Error::UnexpectedEof => "Unexpected end of file".to_string(),
-            Error::UnexpectedChar(c) => format!("Unexpected character: {}", c),
-            Error::Io(e) => format!("IO error: {}", e),
+            Error::UnexpectedToken { token, .. } => write!(f, "Unexpected token: {}", token),
+            Error::MissingColon { key, token, .. } => write!(
+                f,
+                "Expected `:` after dictionary key `{}`, found {}",
+                key,
+                describe_token(token)
+            ),
+            Error::InvalidKey { token, .. } => {
+                write!(
+                    f,
+                    "Expected a dictionary key, found {}",
+                    describe_token(token)
+                )
+            }
+            Error::UnknownFunction { name, .. } => write!(f, "Unknown function: {}", name),
+            Error::UnexpectedEof { .. } => write!(f, "Unexpected end of file"),
+            Error::UnexpectedChar { char, .. } => write!(f, "Unexpected character: {}", char),
+            Error::UnterminatedString { .. } => write!(f, "Unterminated string"),
+            Error::InvalidEscape { sequence, .. } => {
+                write!(f, "Invalid escape sequence: {}", sequence)
+            }
+            Error::InvalidNumber { literal, .. } => write!(f, "Invalid number: {}", literal),
+            Error::NumberOverflow { literal, .. } => {
+                write!(f, "Number out of range: {}", literal)
+            }
+            Error::DuplicateKey { key, first, .. } => write!(
+                f,
+                "Duplicate dictionary key `{}`, first defined at {}",
+                key, first
+            ),
+            Error::InvalidQuery { message, .. } => write!(f, "Invalid query: {}", message),
+            Error::QueryFailed { message, .. } => write!(f, "Query failed: {}", message),
+            Error::Serde { path, message } if path.is_empty() => write!(f, "{}", message),
+            Error::Serde { path, message } => write!(f, "{}: {}", path, message),
+            Error::InvalidSource {
+                format, message, ..
+            } => write!(f, "Invalid {}: {}", format, message),
+            Error::Conversion { path, message } if path.is_empty() => write!(f, "{}", message),
+            Error::Conversion { path, message } => write!(f, "{}: {}", path, message),
+            Error::InvalidSchema { path, message } if path.is_empty() => {
+                write!(f, "Invalid schema: {}", message)
+            }
+            Error::InvalidSchema { path, message } => {
+                write!(f, "Invalid schema: {}: {}", path, message)
+            }
+            Error::FunctionFailed { name, message, .. } => {
+                write!(f, "Function `{}` failed: {}", name, message)
+            }
+            Error::ImportNotFound { path, tried, .. } => {
+                write!(f, "Imported file not found: {} (tried ", path)?;
+                for (i, candidate) in tried.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", candidate.display())?;
+                }
+                write!(f, ")")
+            }
+            Error::ImportFailed { path, source, .. } => {
+                write!(f, "Failed to import {}: {}", path.display(), source)
+            }
+            Error::ImportCycle { chain, .. } => {
+                write!(f, "Import cycle: ")?;
+                for (i, file) in chain.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " -> ")?;
+                    }
+                    write!(f, "{}", file.display())?;
+                }
+                Ok(())
+            }
+            Error::Io(e) => write!(f, "IO error: {}", e),
         }
     }
 }
 
+impl std::error::Error for Error {}
+
 /// Result type returned by Dent.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,91 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use crate::{Imports, Location};
+
+/// Where a function is called from, passed to functions registered with
+/// `Dent::add_context_function` and `Dent::add_fallible_function`.
+#[derive(Clone, Copy, Debug)]
+pub struct FunctionContext<'c> {
+    location: &'c Location,
+    imports: &'c Imports,
+}
+
+impl<'c> FunctionContext<'c> {
+    pub(crate) fn new(location: &'c Location, imports: &'c Imports) -> FunctionContext<'c> {
+        FunctionContext { location, imports }
+    }
+
+    /// Returns the location of the call's function name.
+    pub fn location(&self) -> &'c Location {
+        self.location
+    }
+
+    /// Returns the canonical path of the file the function is called from, or
+    /// `None` if it is called from a string parsed with `Dent::parse`.
+    pub fn file(&self) -> Option<&'c Path> {
+        self.location.file.as_deref()
+    }
+
+    /// Returns the files being parsed, from the one passed to
+    /// `Dent::parse_file` to the one the function is called from.
+    pub(crate) fn imports(&self) -> &'c Imports {
+        self.imports
+    }
+
+    /// Resolves a path relative to the directory of the file the function is
+    /// called from. Absolute paths, and all paths in strings parsed with
+    /// `Dent::parse`, are returned as they are, so they are relative to the
+    /// current directory.
+    ///
+    /// # Examples
+    /// ```
+    /// use dent_parse::{Dent, Value};
+    /// use std::path::Path;
+    ///
+    /// let mut dent = Dent::new(Default::default());
+    /// dent.add_context_function("path", Box::new(|value, context| {
+    ///     let path = context.resolve_path(Path::new(value.as_str().unwrap_or_default()));
+    ///     Value::from(path.to_string_lossy().into_owned())
+    /// }));
+    ///
+    /// assert_eq!(dent.parse("@path \"dict.dent\""), Ok(Value::from("dict.dent")));
+    /// ```
+    pub fn resolve_path(&self, path: &Path) -> PathBuf {
+        match self.file().and_then(Path::parent) {
+            Some(directory) if path.is_relative() => directory.join(path),
+            _ => path.to_path_buf(),
+        }
+    }
+}
+
+/// The error a `FallibleFunction` returns to make parsing fail.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FunctionError {
+    message: String,
+}
+
+impl FunctionError {
+    /// Creates an error with a message saying what is wrong, such as
+    /// `expected a list of numbers`.
+    pub fn new(message: impl Into<String>) -> FunctionError {
+        FunctionError {
+            message: message.into(),
+        }
+    }
+
+    /// Returns the message of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for FunctionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for FunctionError {}
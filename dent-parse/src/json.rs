@@ -0,0 +1,151 @@
+//! Converting between JSON and `Value`s.
+//!
+//! Every JSON document converts to a `Value`:
+//! - `null` becomes `Value::None`,
+//! - numbers become `Value::Int` if they are integers that fit in an `i64`,
+//!   and `Value::Float` otherwise, so `1` and `1.0` stay apart,
+//! - objects become dictionaries with their keys in the same order.
+//!
+//! Strings stay strings, even if they look like a Dent keyword or number,
+//! such as `"true"`, `"none"` or `"42"`. `Emitter` quotes them when the value
+//! is written as Dent, so they are read back as strings.
+//!
+//! The other way around, `Value::None` becomes `null`. JSON has no infinite or
+//! NaN numbers, so converting a `Value` containing one fails with
+//! `Error::Conversion`, carrying the key path of the float, rather than
+//! silently writing `null`.
+//!
+//! # Examples
+//! ```
+//! use dent_parse::{json, Emitter};
+//!
+//! let value = json::from_str(r#"{ "name": "Mario", "title": "none", "rival": null }"#).unwrap();
+//! assert_eq!(
+//!     Emitter::compact().emit(&value),
+//!     "{ name: Mario title: \"none\" rival: none }"
+//! );
+//!
+//! assert_eq!(
+//!     json::to_string(&value).unwrap(),
+//!     r#"{"name":"Mario","title":"none","rival":null}"#
+//! );
+//! ```
+
+use serde_json::{Map, Number};
+
+use crate::{key_path::KeyPath, Dict, Error, Location, OwnedValue, Result, Span, Value};
+
+/// Parses a JSON document into a `Value`.
+///
+/// Fails with `Error::InvalidSource`, located in `json`, if it is not valid
+/// JSON, or with `Error::Conversion` if it contains an integer that does not
+/// fit in an `i64`.
+pub fn from_str(json: &str) -> Result<OwnedValue> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| {
+        // The message of a `serde_json::Error` ends with its position, which
+        // is part of the location instead
+        let message = e.to_string();
+        let message = match message.rfind(" at line ") {
+            Some(i) => message[..i].to_string(),
+            None => message,
+        };
+        Error::InvalidSource {
+            format: "JSON".to_string(),
+            message,
+            location: Location::new(Span::at_line_column(json, e.line(), e.column())),
+        }
+    })?;
+    from_json(&value)
+}
+
+/// Converts a parsed JSON value into a `Value`.
+///
+/// Fails with `Error::Conversion` if it contains an integer that does not fit
+/// in an `i64`.
+pub fn from_json(value: &serde_json::Value) -> Result<OwnedValue> {
+    from_json_at(value, KeyPath::Root)
+}
+
+fn from_json_at(value: &serde_json::Value, path: KeyPath) -> Result<OwnedValue> {
+    Ok(match value {
+        serde_json::Value::Null => Value::None,
+        serde_json::Value::Bool(b) => Value::Bool(*b),
+        serde_json::Value::Number(n) => match (n.as_i64(), n.as_f64()) {
+            (Some(i), _) if !n.is_f64() => Value::Int(i),
+            (_, Some(f)) if n.is_f64() => Value::Float(f),
+            _ => {
+                return Err(Error::Conversion {
+                    path: path.to_string(),
+                    message: format!("integer {} is out of range", n),
+                })
+            }
+        },
+        serde_json::Value::String(s) => Value::from(s.clone()),
+        serde_json::Value::Array(a) => Value::List(
+            a.iter()
+                .enumerate()
+                .map(|(i, v)| from_json_at(v, KeyPath::Index(&path, i)))
+                .collect::<Result<_>>()?,
+        ),
+        serde_json::Value::Object(o) => {
+            let mut dict = Dict::with_capacity(o.len());
+            for (k, v) in o {
+                dict.insert(k.clone().into(), from_json_at(v, KeyPath::Key(&path, k))?);
+            }
+            Value::Dict(dict)
+        }
+    })
+}
+
+/// Converts a `Value` into a JSON value.
+///
+/// Fails with `Error::Conversion` if it contains an infinite or NaN float.
+pub fn to_json(value: &Value) -> Result<serde_json::Value> {
+    to_json_at(value, KeyPath::Root)
+}
+
+fn to_json_at(value: &Value, path: KeyPath) -> Result<serde_json::Value> {
+    Ok(match value {
+        Value::None => serde_json::Value::Null,
+        Value::Str(s) => serde_json::Value::String(s.to_string()),
+        Value::Int(i) => serde_json::Value::Number((*i).into()),
+        Value::Float(f) => match Number::from_f64(*f) {
+            Some(n) => serde_json::Value::Number(n),
+            None => {
+                return Err(Error::Conversion {
+                    path: path.to_string(),
+                    message: format!("{} cannot be represented in JSON", Value::Float(*f)),
+                })
+            }
+        },
+        Value::Bool(b) => serde_json::Value::Bool(*b),
+        Value::List(l) => serde_json::Value::Array(
+            l.iter()
+                .enumerate()
+                .map(|(i, v)| to_json_at(v, KeyPath::Index(&path, i)))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Dict(d) => {
+            let mut map = Map::with_capacity(d.len());
+            for (k, v) in d {
+                map.insert(k.to_string(), to_json_at(v, KeyPath::Key(&path, k))?);
+            }
+            serde_json::Value::Object(map)
+        }
+    })
+}
+
+/// Writes a `Value` as JSON on a single line.
+///
+/// Fails with `Error::Conversion` if it contains an infinite or NaN float.
+pub fn to_string(value: &Value) -> Result<String> {
+    Ok(to_json(value)?.to_string())
+}
+
+/// Writes a `Value` as indented JSON.
+///
+/// Fails with `Error::Conversion` if it contains an infinite or NaN float.
+pub fn to_string_pretty(value: &Value) -> Result<String> {
+    let json = to_json(value)?;
+    Ok(serde_json::to_string_pretty(&json).expect("JSON values can always be written"))
+}
@@ -0,0 +1,25 @@
+use crate::span::write_key;
+
+/// The position of a value in a document being converted, kept as a linked
+/// list through the stack of recursive calls so it only has to be formatted
+/// when an error occurs.
+#[derive(Clone, Copy)]
+pub(crate) enum KeyPath<'p> {
+    Root,
+    Key(&'p KeyPath<'p>, &'p str),
+    Index(&'p KeyPath<'p>, usize),
+}
+
+impl<'p> std::fmt::Display for KeyPath<'p> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeyPath::Root => Ok(()),
+            KeyPath::Key(KeyPath::Root, key) => write_key(f, true, key),
+            KeyPath::Key(parent, key) => {
+                write!(f, "{}", parent)?;
+                write_key(f, false, key)
+            }
+            KeyPath::Index(parent, index) => write!(f, "{}[{}]", parent, index),
+        }
+    }
+}
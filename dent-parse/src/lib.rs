@@ -1,8 +1,47 @@
+#[cfg(any(feature = "yaml", feature = "toml"))]
+mod convert;
+#[cfg(feature = "serde")]
+mod de;
+pub mod diagnostics;
+pub mod document;
+mod emit;
 mod error;
+mod function;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(any(
+    feature = "serde",
+    feature = "json",
+    feature = "toml",
+    feature = "schema"
+))]
+mod key_path;
+mod number;
+pub mod query;
 mod repr;
+#[cfg(feature = "schema")]
+pub mod schema;
+#[cfg(feature = "serde")]
+mod ser;
+mod span;
 mod tokenizer;
+#[cfg(feature = "toml")]
+pub mod toml;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+pub use convert::{ConversionWarning, Converted};
+#[cfg(feature = "serde")]
+pub use de::{from_file, from_str, from_value};
+use diagnostics::Diagnostic;
+pub use emit::{Emitter, Quoting};
 pub use error::*;
+pub use function::{FunctionContext, FunctionError};
+use number::number_value;
 pub use repr::*;
+#[cfg(feature = "serde")]
+pub use ser::{to_string, to_string_pretty, to_value};
+pub use span::*;
 use tokenizer::{Token, Tokenizer};
 
 #[cfg(test)]
@@ -10,9 +49,12 @@ mod tests;
 
 use std::{
     collections::HashMap,
-    io::Read,
+    ffi::OsStr,
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
 };
 
 /// Alias for a trait object that represents a function that can be called from
@@ -24,6 +66,9 @@ use std::{
 /// A Dent function can only take a single argument, for simplicity.
 /// If you need to pass multiple arguments, you can use a list or dictionary.
 ///
+/// A `Function` cannot fail. Use a `FallibleFunction` to report invalid
+/// arguments as errors rather than returning `Value::None`.
+///
 /// # Examples
 /// ```
 /// use dent_parse::{Dent, Value, Function};
@@ -56,6 +101,50 @@ use std::{
 /// ```
 pub type Function = dyn for<'a> Fn(&Value<'a>) -> Value<'a> + Send + Sync;
 
+/// Alias for a trait object that represents a function that can be called from
+/// Dent, and is told where it is called from.
+///
+/// Like a `Function`, but it also takes a `FunctionContext`, which has the
+/// location of the call and the file it is in. Register it with
+/// `Dent::add_context_function`.
+pub type ContextFunction = dyn for<'a> Fn(&Value<'a>, &FunctionContext) -> Value<'a> + Send + Sync;
+
+/// Alias for a trait object that represents a function that can be called from
+/// Dent, and can fail.
+///
+/// Like a `ContextFunction`, but it returns a `Result`. The parser fails with
+/// `Error::FunctionFailed`, located at the call, if it returns a
+/// `FunctionError`. Register it with `Dent::add_fallible_function`.
+pub type FallibleFunction = dyn for<'a> Fn(&Value<'a>, &FunctionContext) -> std::result::Result<Value<'a>, FunctionError>
+    + Send
+    + Sync;
+
+/// The form every function is stored in, which can fail with an error that
+/// the parser returns.
+type StoredFunction =
+    dyn for<'a> Fn(&Value<'a>, &FunctionContext) -> Result<Value<'a>> + Send + Sync;
+
+/// Alias for a trait object that receives the warnings produced while parsing,
+/// registered with `Dent::set_diagnostic_handler`.
+pub type DiagnosticHandler = dyn Fn(&Diagnostic) + Send + Sync;
+
+/// What the parser does when a dictionary contains the same key twice, as in
+/// `{ a: 1 a: 2 }`.
+///
+/// The value of a duplicate entry is always parsed, so functions it calls are
+/// run even if it is discarded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DuplicateKeys {
+    /// Fail with `Error::DuplicateKey`.
+    Error,
+    /// Keep the last value, and report a warning to the diagnostic handler.
+    Warn,
+    /// Silently keep the first value.
+    KeepFirst,
+    /// Silently keep the last value.
+    KeepLast,
+}
+
 /// Main struct for parsing Dent.
 ///
 /// This struct is used to parse Dent files and strings. It can also be used to
@@ -68,7 +157,7 @@ pub type Function = dyn for<'a> Fn(&Value<'a>) -> Value<'a> + Send + Sync;
 ///
 /// let parser = Dent::default();
 ///
-/// assert_eq!(parser.parse("foo"), Ok(Value::Str("foo")));
+/// assert_eq!(parser.parse("foo"), Ok(Value::Str("foo".into())));
 /// assert_eq!(parser.parse("[ 1 2 3 ]"), Ok(Value::List(vec![
 ///     Value::Int(1),
 ///     Value::Int(2),
@@ -79,40 +168,157 @@ pub struct Dent {
     internal: Arc<Mutex<DentInternal>>,
 }
 
-struct Import {
-    src: &'static str,
-    value: Value<'static>,
+struct DentInternal {
+    functions: HashMap<String, Arc<StoredFunction>>,
+    import_map: HashMap<PathBuf, CachedImport>,
+    include_dirs: Vec<PathBuf>,
+    strict: bool,
+    duplicate_keys: Option<DuplicateKeys>,
+    diagnostic_handler: Option<Arc<DiagnosticHandler>>,
 }
 
-impl Drop for Import {
-    fn drop(&mut self) {
-        unsafe {
-            let b = Box::from_raw(self.src as *const str as *mut str);
-            std::mem::drop(b);
-        }
-    }
+/// The value of a parsed file, with every file it imports, directly or not.
+struct CachedImport {
+    value: OwnedValue,
+    imports: Vec<Arc<Path>>,
 }
 
-struct DentInternal {
-    functions: HashMap<String, Arc<Function>>,
-    import_map: HashMap<PathBuf, Import>,
+impl DentInternal {
+    fn duplicate_keys(&self) -> DuplicateKeys {
+        match self.duplicate_keys {
+            Some(policy) => policy,
+            None if self.strict => DuplicateKeys::Error,
+            None => DuplicateKeys::KeepLast,
+        }
+    }
 }
 
 struct ParserState<'s> {
     tokenizer: Tokenizer<'s>,
     token: Token<'s>,
+    span: Span,
+    file: Option<Arc<Path>>,
+    /// The locations of the values parsed so far, if they are recorded, and
+    /// the key path of the value being parsed
+    locations: Option<Locations>,
+    path: String,
+    imports: Imports,
+}
+
+/// The files being parsed, from the first one to the current one, which
+/// `@import` checks for cycles.
+#[derive(Debug)]
+pub(crate) struct Imports {
+    files: Vec<Arc<Path>>,
+    /// Every file imported while parsing the last of `files`, directly or not
+    imported: Mutex<Vec<Arc<Path>>>,
+    /// The index in `files` of the outermost file whose import was cut off
+    /// as recursive, or `usize::MAX` if none was. The values of the files
+    /// after it depend on the files importing them, so they are not cached.
+    cut_off: AtomicUsize,
+}
+
+impl Imports {
+    fn new(files: Vec<Arc<Path>>) -> Imports {
+        Imports {
+            files,
+            imported: Mutex::new(Vec::new()),
+            cut_off: AtomicUsize::new(usize::MAX),
+        }
+    }
+
+    /// Records that a file is imported, along with the files it imports.
+    fn record_import(&self, file: &Arc<Path>, imports: &[Arc<Path>]) {
+        let mut imported = self.imported.lock().unwrap();
+        for file in std::iter::once(file).chain(imports) {
+            if !imported.contains(file) {
+                imported.push(file.clone());
+            }
+        }
+    }
+
+    fn record_cut_off(&self, index: usize) {
+        self.cut_off.fetch_min(index, Ordering::Relaxed);
+    }
+
+    fn cut_off(&self) -> usize {
+        self.cut_off.load(Ordering::Relaxed)
+    }
 }
 
 impl<'s> ParserState<'s> {
-    fn new(mut tokenizer: Tokenizer<'s>) -> Result<Self> {
+    fn new(mut tokenizer: Tokenizer<'s>, file: Option<Arc<Path>>) -> Result<Self> {
         let token = tokenizer.next()?;
-        Ok(ParserState { tokenizer, token })
+        let span = tokenizer.span();
+        let imports = Imports::new(file.iter().cloned().collect());
+        Ok(ParserState {
+            tokenizer,
+            token,
+            span,
+            file,
+            locations: None,
+            path: String::new(),
+            imports,
+        })
     }
 
     fn next(&mut self) -> Result<()> {
         self.token = self.tokenizer.next()?;
+        self.span = self.tokenizer.span();
         Ok(())
     }
+
+    fn location(&self) -> Location {
+        Location {
+            file: self.file.clone(),
+            span: self.span,
+        }
+    }
+
+    fn unexpected_token(&self) -> Error {
+        Error::UnexpectedToken {
+            token: self.token.type_name(),
+            location: self.location(),
+        }
+    }
+
+    /// Records the location of the current token as that of the value at the
+    /// current key path, if locations are recorded.
+    fn record_location(&mut self) {
+        if self.locations.is_some() {
+            let location = self.location();
+            if let Some(locations) = &mut self.locations {
+                locations.insert(self.path.clone(), location);
+            }
+        }
+    }
+
+    /// Appends a dictionary key to the current key path, if locations are
+    /// recorded, returning the length to truncate it back to.
+    fn push_key(&mut self, key: &str) -> usize {
+        let len = self.path.len();
+        if self.locations.is_some() {
+            let first = self.path.is_empty();
+            let _ = span::write_key(&mut self.path, first, key);
+        }
+        len
+    }
+
+    /// Appends a list index to the current key path, if locations are
+    /// recorded, returning the length to truncate it back to.
+    fn push_index(&mut self, index: usize) -> usize {
+        let len = self.path.len();
+        if self.locations.is_some() {
+            self.path.push_str(&format!("[{}]", index));
+        }
+        len
+    }
+
+    fn unexpected_eof(&self) -> Error {
+        Error::UnexpectedEof {
+            location: self.location(),
+        }
+    }
 }
 
 impl Dent {
@@ -123,12 +329,16 @@ impl Dent {
     pub fn new(functions: HashMap<String, Box<Function>>) -> Dent {
         let functions = functions
             .into_iter()
-            .map(|(k, v)| (k, Arc::new(v) as Arc<Function>))
+            .map(|(k, v)| (k, Self::with_context(v)))
             .collect();
 
         let internal = DentInternal {
             functions,
             import_map: HashMap::new(),
+            include_dirs: Vec::new(),
+            strict: false,
+            duplicate_keys: None,
+            diagnostic_handler: None,
         };
 
         Dent {
@@ -140,33 +350,66 @@ impl Dent {
     ///
     /// This function adds the following functions:
     /// - `import`: Imports a Dent file. Takes a string (file path) as an argument.
+    ///   Relative paths are resolved against the directory of the importing
+    ///   file, or the current directory in strings parsed with `Dent::parse`,
+    ///   and then against the include directories, in order. Fails with
+    ///   `Error::ImportNotFound` if there is no such file, and with
+    ///   `Error::ImportFailed` if it cannot be read or parsed.
+    ///
+    ///   Importing a file that is already being imported, directly or through
+    ///   other files, fails with `Error::ImportCycle`. To allow it, write the
+    ///   import as `@import { path: "file.dent" recursive: true }`, and the
+    ///   import that closes the cycle is read as `none`.
     /// - `merge`: Merges a list of lists or a list of dicts into a single list or dict.
     pub fn add_builtins(&mut self) {
-        let internal = self.internal.clone();
+        // The function is stored in the parser itself, so holding a strong
+        // reference here would keep the parser alive forever
+        let internal = Arc::downgrade(&self.internal);
 
         let outer_functions = &mut self.internal.lock().unwrap().functions;
 
         outer_functions.insert(
             "import".to_string(),
-            Arc::new(move |value| {
-                if let Value::Str(s) = value {
-                    let path = Path::new(s);
+            Arc::new(move |value, context| {
+                let internal = match Weak::upgrade(&internal) {
+                    Some(internal) => internal,
+                    None => return Ok(Value::None),
+                };
 
-                    let value = Self::import(internal.clone(), path);
+                let (path, recursive) = Self::import_argument(value, context)?;
+                let path = Self::find_import(&internal, path, context)?;
 
-                    match value {
-                        Ok(v) => v,
-                        Err(_) => Value::None,
+                let failed = |e: Error| Error::ImportFailed {
+                    path: path.clone(),
+                    source: Box::new(e),
+                    location: context.location().clone(),
+                };
+
+                let canonical = path.canonicalize().map_err(|e| failed(e.into()))?;
+                let imports = context.imports();
+                if let Some(start) = imports.files.iter().position(|f| **f == *canonical) {
+                    if recursive {
+                        imports.record_import(&imports.files[start], &[]);
+                        imports.record_cut_off(start);
+                        return Ok(Value::None);
                     }
-                } else {
-                    Value::None
+                    return Err(Error::ImportCycle {
+                        chain: imports.files[start..]
+                            .iter()
+                            .map(|f| f.to_path_buf())
+                            .chain([canonical])
+                            .collect(),
+                        location: context.location().clone(),
+                    });
                 }
+
+                Self::import(internal, &canonical, Some(imports)).map_err(failed)
             }),
         );
 
         outer_functions.insert(
             "merge".to_string(),
-            Arc::new(move |value| {
+            Self::with_context(Box::new(|value| {
                 // we want either a list of dicts or a list of lists
                 if let Value::List(values) = value {
                     let mut result = Vec::new();
@@ -206,7 +449,7 @@ impl Dent {
                 } else {
                     Value::None
                 }
-            }),
+            })),
         );
     }
 
@@ -231,13 +474,182 @@ impl Dent {
     /// assert_eq!(dent.parse("@count [ 1 2 3 ]"), Ok(Value::Int(3)));
     /// ```
     pub fn add_function(&mut self, name: &str, function: Box<Function>) {
-        let function = Arc::new(function);
+        let function = Self::with_context(function);
 
         let outer_functions = &mut self.internal.lock().unwrap().functions;
 
         outer_functions.insert(name.to_string(), function);
     }
 
+    /// Adds a function that is told where it is called from to the parser.
+    ///
+    /// Like `Dent::add_function`, but the function also takes a
+    /// `FunctionContext`, with the location of the call and the file it is
+    /// in, for example to resolve paths relative to that file.
+    ///
+    /// # Examples
+    /// ```
+    /// use dent_parse::{Dent, Value};
+    ///
+    /// let mut dent = Dent::default();
+    /// dent.add_context_function("line", Box::new(|_, context| {
+    ///     Value::Int(context.location().span.line as i64)
+    /// }));
+    /// assert_eq!(dent.parse("[\n    @line none\n]"), Ok(Value::List(vec![Value::Int(2)])));
+    /// ```
+    pub fn add_context_function(&mut self, name: &str, function: Box<ContextFunction>) {
+        let function: Arc<StoredFunction> =
+            Arc::new(move |value, context| Ok(function(value, context)));
+
+        let outer_functions = &mut self.internal.lock().unwrap().functions;
+
+        outer_functions.insert(name.to_string(), function);
+    }
+
+    /// Adds a function that can fail to the parser.
+    ///
+    /// Like `Dent::add_context_function`, but the function returns a
+    /// `Result`. If it returns an error, parsing fails with
+    /// `Error::FunctionFailed`, which has the error's message and the location
+    /// of the call.
+    ///
+    /// # Examples
+    /// ```
+    /// use dent_parse::{Dent, Error, FunctionError, Value};
+    ///
+    /// let mut dent = Dent::default();
+    /// dent.add_fallible_function("double", Box::new(|value, _| match value {
+    ///     Value::Int(i) => i
+    ///         .checked_mul(2)
+    ///         .map(Value::Int)
+    ///         .ok_or_else(|| FunctionError::new("the result is too large")),
+    ///     v => Err(FunctionError::new(format!("expected an int, found {}", v.type_name()))),
+    /// }));
+    ///
+    /// assert_eq!(dent.parse("@double 21"), Ok(Value::Int(42)));
+    /// assert_eq!(
+    ///     dent.parse("[ @double two ]").unwrap_err().to_string(),
+    ///     "1:4: Function `double` failed: expected an int, found string"
+    /// );
+    /// ```
+    pub fn add_fallible_function(&mut self, name: &str, function: Box<FallibleFunction>) {
+        let name = name.to_string();
+        let outer_name = name.clone();
+
+        let function: Arc<StoredFunction> = Arc::new(move |value, context| {
+            function(value, context).map_err(|e| Error::FunctionFailed {
+                name: name.clone(),
+                message: e.message().to_string(),
+                location: context.location().clone(),
+            })
+        });
+
+        let outer_functions = &mut self.internal.lock().unwrap().functions;
+
+        outer_functions.insert(outer_name, function);
+    }
+
+    /// Wraps a function that does not need to know where it is called from.
+    fn with_context(function: Box<Function>) -> Arc<StoredFunction> {
+        Arc::new(move |value, _| Ok(function(value)))
+    }
+
+    /// Adds a directory to search for imported files in.
+    ///
+    /// A relative path in an `@import` is first resolved against the
+    /// directory of the importing file, and then against each include
+    /// directory, in the order they were added. The first file that exists is
+    /// imported.
+    ///
+    /// # Examples
+    /// ```
+    /// use dent_parse::{Dent, Value};
+    ///
+    /// let mut dent = Dent::default();
+    /// dent.add_include_dir("examples/dent");
+    /// assert_eq!(
+    ///     dent.parse("@import \"dict.dent\"").unwrap()["name"],
+    ///     Value::Str("Mario".into())
+    /// );
+    /// ```
+    pub fn add_include_dir<P: Into<PathBuf>>(&mut self, dir: P) {
+        self.internal.lock().unwrap().include_dirs.push(dir.into());
+    }
+
+    /// Adds the directories in a list of paths as include directories, as
+    /// with `Dent::add_include_dir`.
+    ///
+    /// The directories are separated like in `PATH`, with `:` on Unix and `;`
+    /// on Windows. Empty entries are skipped.
+    pub fn add_include_dirs_from_paths<S: AsRef<OsStr>>(&mut self, paths: S) {
+        let dirs = std::env::split_paths(&paths).filter(|d| !d.as_os_str().is_empty());
+        self.internal.lock().unwrap().include_dirs.extend(dirs);
+    }
+
+    /// Adds the directories listed in the `DENT_PATH` environment variable as
+    /// include directories, as with `Dent::add_include_dirs_from_paths`.
+    pub fn add_include_dirs_from_env(&mut self) {
+        if let Some(dent_path) = std::env::var_os("DENT_PATH") {
+            self.add_include_dirs_from_paths(dent_path);
+        }
+    }
+
+    /// Enables or disables strict mode.
+    ///
+    /// Strict mode rejects input that is valid but likely a mistake. Currently,
+    /// it makes duplicate dictionary keys an error, unless a different policy
+    /// is chosen with `Dent::set_duplicate_keys`.
+    ///
+    /// # Examples
+    /// ```
+    /// use dent_parse::{Dent, Error, Value};
+    ///
+    /// let mut dent = Dent::default();
+    /// assert_eq!(dent.parse("{ a: 1 a: 2 }").unwrap()["a"], Value::Int(2));
+    ///
+    /// dent.set_strict(true);
+    /// assert!(matches!(
+    ///     dent.parse("{ a: 1 a: 2 }"),
+    ///     Err(Error::DuplicateKey { .. })
+    /// ));
+    /// ```
+    pub fn set_strict(&mut self, strict: bool) {
+        self.internal.lock().unwrap().strict = strict;
+    }
+
+    /// Sets what the parser does with duplicate dictionary keys.
+    ///
+    /// By default, the last value is kept, or an error is returned in strict
+    /// mode.
+    pub fn set_duplicate_keys(&mut self, policy: DuplicateKeys) {
+        self.internal.lock().unwrap().duplicate_keys = Some(policy);
+    }
+
+    /// Sets the handler warnings produced while parsing are reported to.
+    ///
+    /// Without a handler, warnings are discarded.
+    ///
+    /// # Examples
+    /// ```
+    /// use dent_parse::{Dent, DuplicateKeys};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let warnings = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let mut dent = Dent::default();
+    /// dent.set_duplicate_keys(DuplicateKeys::Warn);
+    /// let w = warnings.clone();
+    /// dent.set_diagnostic_handler(Box::new(move |d| {
+    ///     w.lock().unwrap().push(d.message.clone());
+    /// }));
+    ///
+    /// dent.parse("{ a: 1 a: 2 }").unwrap();
+    /// assert_eq!(*warnings.lock().unwrap(), vec!["duplicate dictionary key `a`"]);
+    /// ```
+    pub fn set_diagnostic_handler(&mut self, handler: Box<DiagnosticHandler>) {
+        self.internal.lock().unwrap().diagnostic_handler = Some(Arc::from(handler));
+    }
+
     /// Parses a Dent string.
     ///
     /// The returned value is a zero-copy representation of the parsed Dent
@@ -251,7 +663,7 @@ impl Dent {
     ///
     /// let parser = Dent::default();
     ///
-    /// assert_eq!(parser.parse("foo"), Ok(Value::Str("foo")));
+    /// assert_eq!(parser.parse("foo"), Ok(Value::Str("foo".into())));
     /// assert_eq!(parser.parse("2"), Ok(Value::Int(2)));
     /// assert_eq!(parser.parse("2.0"), Ok(Value::Float(2.0)));
     /// assert_eq!(parser.parse("true"), Ok(Value::Bool(true)));
@@ -259,17 +671,56 @@ impl Dent {
     pub fn parse<'s>(&self, input: &'s str) -> Result<Value<'s>> {
         let tokenizer = Tokenizer::new(input);
 
-        let mut state = ParserState::new(tokenizer)?;
+        let mut state = ParserState::new(tokenizer, None)?;
 
         Self::parse_value(self.internal.clone(), &mut state)
     }
 
+    /// Parses a Dent string, and returns the locations of its values along
+    /// with them.
+    ///
+    /// The locations are used to point at the values in messages about them,
+    /// such as the violations found by `schema::Schema::validate_located`.
+    ///
+    /// # Examples
+    /// ```
+    /// use dent_parse::Dent;
+    ///
+    /// let (_, locations) = Dent::default()
+    ///     .parse_with_locations("{\n    skills: [ jumps grows ]\n}")
+    ///     .unwrap();
+    /// assert_eq!(locations.get("skills[1]").unwrap().span.line, 2);
+    /// assert_eq!(locations.get("skills[1]").unwrap().span.column, 21);
+    /// ```
+    pub fn parse_with_locations<'s>(&self, input: &'s str) -> Result<(Value<'s>, Locations)> {
+        let tokenizer = Tokenizer::new(input);
+
+        let mut state = ParserState::new(tokenizer, None)?;
+        state.locations = Some(Locations::default());
+
+        let value = Self::parse_value(self.internal.clone(), &mut state)?;
+        Ok((value, state.locations.unwrap_or_default()))
+    }
+
     /// Parses a Dent file.
     ///
-    /// The returned value is a zero-copy representation of the parsed Dent. All strings
-    /// in the returned value borrow from the input file.
+    /// The returned value owns all of its strings, so it does not borrow from
+    /// the file contents, which are dropped after parsing. This costs a copy
+    /// of every string in the file, but keeps values free of lifetimes tied
+    /// to the parser.
+    ///
+    /// The parsed value of every file, whether parsed by this function or
+    /// imported with `@import`, is cached in the parser until it is dropped or
+    /// `Dent::clear_imports` is called. Parsing the same file again returns a
+    /// copy of the cached value, and changes to the file are not seen. Nothing
+    /// is ever evicted, so the cache grows with every file parsed, and a
+    /// long-lived parser that reads many files should clear it from time to
+    /// time. Files whose value depends on how they were imported, because a
+    /// recursive `@import` in them was cut off, are not cached.
     ///
-    /// The file is read and stored in memory for the lifetime of the program.
+    /// Errors encountered while parsing the file carry its canonical path in
+    /// their `Location`. Fails with `Error::Io` if the file cannot be read,
+    /// and with `Error::ImportFailed` if a file it imports cannot be.
     ///
     /// # Examples
     /// ```
@@ -280,71 +731,180 @@ impl Dent {
     /// let value = parser.parse_file("examples/dent/dict.dent").unwrap();
     /// assert_eq!(value, Value::Dict(
     ///     vec![
-    ///         ("name", Value::Str("Mario")),
+    ///         ("name".into(), Value::Str("Mario".into())),
     ///         (
-    ///             "skills",
-    ///             Value::List(vec![Value::Str("jumps"), Value::Str("grows")])
+    ///             "skills".into(),
+    ///             Value::List(vec![Value::Str("jumps".into()), Value::Str("grows".into())])
     ///         ),
-    ///         ("age", Value::Int(35)),
-    ///         ("alive", Value::Bool(true)),
+    ///         ("age".into(), Value::Int(35)),
+    ///         ("alive".into(), Value::Bool(true)),
     ///     ].into_iter().collect()
     /// ));
     /// ```
-    pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<Value<'static>> {
-        Self::import(self.internal.clone(), path)
+    pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<OwnedValue> {
+        Self::import(self.internal.clone(), path, None)
     }
 
-    fn import<P: AsRef<Path>>(
-        internal: Arc<Mutex<DentInternal>>,
+    /// Parses a Dent file, and returns the locations of its values along with
+    /// them.
+    ///
+    /// Unlike `Dent::parse_file`, the file is always read again, as the cache
+    /// holds no locations. Files it imports are cached as usual.
+    pub fn parse_file_with_locations<P: AsRef<Path>>(
+        &self,
         path: P,
-    ) -> Result<Value<'static>> {
-        let path = if let Ok(path) = path.as_ref().canonicalize() {
-            path
-        } else {
-            return Ok(Value::None);
-        };
+    ) -> Result<(OwnedValue, Locations)> {
+        let path: Arc<Path> = Arc::from(path.as_ref().canonicalize()?);
+        let contents = std::fs::read_to_string(&path)?;
 
-        let mut ilock = internal.lock().unwrap();
-        let import_map = &mut ilock.import_map;
-        if let Some(value) = import_map.get(&path) {
-            return Ok(value.value.clone());
-        }
+        let tokenizer = Tokenizer::new(&contents);
 
-        import_map.insert(
-            path.clone(),
-            Import {
-                src: "",
-                value: Value::None,
-            },
-        );
+        ParserState::new(tokenizer, Some(path.clone()))
+            .and_then(|mut state| {
+                state.locations = Some(Locations::default());
+                let value = Self::parse_value(self.internal.clone(), &mut state)?;
+                Ok((value.into_owned(), state.locations.unwrap_or_default()))
+            })
+            .map_err(|e| e.with_file(&path))
+    }
 
-        drop(ilock);
+    /// Forgets the cached values of all parsed and imported files, so they are
+    /// read again the next time they are parsed or imported.
+    ///
+    /// Use this to pick up changes to files, for example when reloading
+    /// configuration.
+    pub fn clear_imports(&self) {
+        self.internal.lock().unwrap().import_map.clear();
+    }
 
-        let mut file = std::fs::File::open(&path).unwrap();
+    /// Reads the argument of `@import`, which is a path, or a dictionary with
+    /// the path and whether the import may be recursive.
+    fn import_argument<'v>(
+        value: &'v Value,
+        context: &FunctionContext,
+    ) -> Result<(&'v Path, bool)> {
+        let failed = |message: String| Error::FunctionFailed {
+            name: "import".to_string(),
+            message,
+            location: context.location().clone(),
+        };
 
-        let mut contents = String::new();
+        let options = match value {
+            Value::Str(path) => return Ok((Path::new(path.as_ref()), false)),
+            Value::Dict(options) => options,
+            v => {
+                return Err(failed(format!(
+                    "expected a path, or a dictionary with a path, found {}",
+                    v.type_name()
+                )))
+            }
+        };
 
-        file.read_to_string(&mut contents).unwrap();
+        if let Some(key) = options.keys().find(|k| *k != "path" && *k != "recursive") {
+            return Err(failed(format!("unknown import option `{}`", key)));
+        }
+        let path = match options.get("path") {
+            Some(Value::Str(path)) => Path::new(path.as_ref()),
+            Some(v) => return Err(failed(format!("expected a path, found {}", v.type_name()))),
+            None => return Err(failed("the path of the file is missing".to_string())),
+        };
+        let recursive = match options.get("recursive") {
+            Some(Value::Bool(recursive)) => *recursive,
+            Some(v) => {
+                return Err(failed(format!(
+                    "expected `recursive` to be a bool, found {}",
+                    v.type_name()
+                )))
+            }
+            None => false,
+        };
+        Ok((path, recursive))
+    }
 
-        let static_contents = Box::leak(contents.into_boxed_str());
+    /// Finds the file an `@import` refers to, trying the path relative to the
+    /// importing file before the include directories.
+    fn find_import(
+        internal: &Arc<Mutex<DentInternal>>,
+        path: &Path,
+        context: &FunctionContext,
+    ) -> Result<PathBuf> {
+        let mut tried = vec![context.resolve_path(path)];
+        if path.is_relative() {
+            let ilock = internal.lock().unwrap();
+            tried.extend(ilock.include_dirs.iter().map(|dir| dir.join(path)));
+        }
 
-        let tokenizer = Tokenizer::new(static_contents);
+        match tried.iter().find(|candidate| candidate.is_file()) {
+            Some(found) => Ok(found.clone()),
+            None => Err(Error::ImportNotFound {
+                path: path.to_string_lossy().into_owned(),
+                tried,
+                location: context.location().clone(),
+            }),
+        }
+    }
+
+    /// Parses a file, or returns its cached value. `importers` are the files
+    /// being parsed that import it, if it is imported.
+    fn import<P: AsRef<Path>>(
+        internal: Arc<Mutex<DentInternal>>,
+        path: P,
+        importers: Option<&Imports>,
+    ) -> Result<OwnedValue> {
+        let path = path.as_ref().canonicalize()?;
+        let file: Arc<Path> = Arc::from(path.as_path());
+
+        // A cached value cannot be used if one of the files it imports is
+        // being parsed, as importing it again makes a cycle
+        if let Some(cached) = internal.lock().unwrap().import_map.get(&path) {
+            match importers {
+                Some(importers) if cached.imports.iter().any(|f| importers.files.contains(f)) => {}
+                Some(importers) => {
+                    importers.record_import(&file, &cached.imports);
+                    return Ok(cached.value.clone());
+                }
+                None => return Ok(cached.value.clone()),
+            }
+        }
 
-        let mut state = ParserState::new(tokenizer).unwrap();
+        let contents = std::fs::read_to_string(&path)?;
 
-        let value = Self::parse_value(internal.clone(), &mut state);
+        let tokenizer = Tokenizer::new(&contents);
 
-        let value = match value {
-            Ok(v) => v,
-            Err(_) => Value::None,
-        };
+        let files: Vec<_> = importers
+            .map_or(&[][..], |i| &i.files)
+            .iter()
+            .cloned()
+            .chain([file.clone()])
+            .collect();
+        let depth = files.len() - 1;
 
-        let mut ilock = internal.lock().unwrap();
-        let import_map = &mut ilock.import_map;
+        let (value, imports) = ParserState::new(tokenizer, Some(file.clone()))
+            .and_then(|mut state| {
+                state.imports = Imports::new(files);
+                let value = Self::parse_value(internal.clone(), &mut state)?;
+                Ok((value.into_owned(), state.imports))
+            })
+            .map_err(|e| e.with_file(&file))?;
+        let cut_off = imports.cut_off();
+        let imports = imports.imported.into_inner().unwrap();
 
-        let i = import_map.get_mut(&path).unwrap();
-        i.src = static_contents;
-        i.value = value.clone();
+        if let Some(importers) = importers {
+            importers.record_import(&file, &imports);
+        }
+
+        // If an import of one of the importers was cut off, the file reads
+        // differently when it is imported some other way
+        match importers {
+            Some(importers) if cut_off < depth => importers.record_cut_off(cut_off),
+            _ => {
+                let cached = CachedImport {
+                    value: value.clone(),
+                    imports,
+                };
+                internal.lock().unwrap().import_map.insert(path, cached);
+            }
+        }
 
         Ok(value)
     }
@@ -353,29 +913,38 @@ impl Dent {
         internal: Arc<Mutex<DentInternal>>,
         state: &mut ParserState<'s>,
     ) -> Result<Value<'s>> {
-        let v = match state.token {
+        if state.token != Token::Comment {
+            state.record_location();
+        }
+
+        let v = match &state.token {
             Token::Eof => Ok(Value::None),
             Token::At => {
                 state.next()?;
-                if let Token::String(s) = state.token {
+                if let Token::String(s) = &state.token {
+                    let s = s.clone();
+                    let location = state.location();
                     state.next()?;
-                    let function = internal
-                        .lock()
-                        .unwrap()
-                        .functions
-                        .get(&s.to_string())
-                        .cloned();
+                    let function = internal.lock().unwrap().functions.get(s.as_ref()).cloned();
                     if let Some(function) = function {
-                        let value = Self::parse_value(internal.clone(), state)?;
-                        Ok(function(&value))
+                        // The argument is not part of the result, so its
+                        // locations are not recorded
+                        let locations = state.locations.take();
+                        let value = Self::parse_value(internal.clone(), state);
+                        state.locations = locations;
+                        function(&value?, &FunctionContext::new(&location, &state.imports))
                     } else {
-                        Err(Error::UnknownFunction(s.to_string()))
+                        Err(Error::UnknownFunction {
+                            name: s.to_string(),
+                            location,
+                        })
                     }
                 } else {
-                    Err(Error::UnexpectedToken(state.token.type_name()))
+                    Err(state.unexpected_token())
                 }
             }
             Token::String(s) => {
+                let s = s.clone();
                 state.next()?;
                 Ok(Value::Str(s))
             }
@@ -384,58 +953,119 @@ impl Dent {
                 let mut values = Vec::new();
                 while state.token != Token::CloseBracket {
                     if state.token == Token::Eof {
-                        return Err(Error::UnexpectedEof);
+                        return Err(state.unexpected_eof());
                     }
+                    let len = state.push_index(values.len());
                     values.push(Self::parse_value(internal.clone(), state)?);
+                    state.path.truncate(len);
                 }
                 state.next()?;
                 Ok(Value::List(values))
             }
             Token::OpenBrace => {
                 state.next()?;
-                let mut values = HashMap::new();
+                let mut values = Dict::new();
+                let mut key_locations: Vec<Location> = Vec::new();
                 while state.token != Token::CloseBrace {
                     if state.token == Token::Eof {
-                        return Err(Error::UnexpectedEof);
+                        return Err(state.unexpected_eof());
                     }
-                    if let Token::String(s) = state.token {
+                    if let Some(s) = state.token.key() {
+                        let key_location = state.location();
                         state.next()?;
                         if state.token != Token::Colon {
-                            return Err(Error::UnexpectedToken(state.token.type_name()));
+                            return Err(Error::MissingColon {
+                                key: s.to_string(),
+                                token: state.token.type_name(),
+                                location: state.location(),
+                            });
                         }
                         state.next()?;
-                        values.insert(s, Self::parse_value(internal.clone(), state)?);
+                        let len = state.push_key(&s);
+                        let value = Self::parse_value(internal.clone(), state)?;
+                        state.path.truncate(len);
+
+                        if let Some(index) = values.get_index_of(&s) {
+                            let first = key_locations[index].clone();
+                            if !Self::keep_duplicate(&internal, &s, first, key_location)? {
+                                continue;
+                            }
+                            values.insert(s, value);
+                        } else {
+                            values.insert(s, value);
+                            key_locations.push(key_location);
+                        }
                     } else {
-                        return Err(Error::UnexpectedToken(state.token.type_name()));
+                        return Err(Error::InvalidKey {
+                            token: state.token.type_name(),
+                            location: state.location(),
+                        });
                     }
                 }
                 state.next()?;
                 Ok(Value::Dict(values))
             }
             Token::Number(n) => {
+                let n = *n;
+                let location = state.location();
                 state.next()?;
-                if let Ok(i) = n.parse::<i64>() {
-                    Ok(Value::Int(i))
-                } else if let Ok(f) = n.parse::<f64>() {
-                    Ok(Value::Float(f))
-                } else {
-                    panic!("Tokenizer returned invalid number: {}", n);
-                }
+                number_value(n, location)
             }
             Token::Bool(b) => {
+                let b = *b;
                 state.next()?;
                 Ok(Value::Bool(b))
             }
+            Token::None => {
+                state.next()?;
+                Ok(Value::None)
+            }
             Token::Comment => {
                 state.next()?;
                 Self::parse_value(internal, state)
             }
-            _ => Err(Error::UnexpectedToken(state.token.type_name())),
+            _ => Err(state.unexpected_token()),
         };
         v
     }
 }
 
+impl Dent {
+    /// Applies the duplicate key policy to a repeated key, returning whether
+    /// the new value should replace the old one.
+    fn keep_duplicate(
+        internal: &Arc<Mutex<DentInternal>>,
+        key: &str,
+        first: Location,
+        location: Location,
+    ) -> Result<bool> {
+        let (policy, handler) = {
+            let ilock = internal.lock().unwrap();
+            (ilock.duplicate_keys(), ilock.diagnostic_handler.clone())
+        };
+
+        match policy {
+            DuplicateKeys::Error => Err(Error::DuplicateKey {
+                key: key.to_string(),
+                first,
+                location,
+            }),
+            DuplicateKeys::Warn => {
+                if let Some(handler) = handler {
+                    handler(
+                        &Diagnostic::warning(format!("duplicate dictionary key `{}`", key))
+                            .with_primary(location, "overrides the earlier value")
+                            .with_secondary(first, "first defined here"),
+                    );
+                }
+                Ok(true)
+            }
+            DuplicateKeys::KeepFirst => Ok(false),
+            DuplicateKeys::KeepLast => Ok(true),
+        }
+    }
+}
+
 impl Default for Dent {
     fn default() -> Self {
         let mut s = Self::new(HashMap::new());
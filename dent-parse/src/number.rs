@@ -0,0 +1,163 @@
+use crate::{Error, Location, OwnedValue, Value};
+
+/// A parsed numeric literal.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+/// Why a numeric literal could not be parsed.
+#[derive(Debug, PartialEq)]
+pub(crate) enum NumberError {
+    /// The literal does not follow the number grammar
+    Invalid,
+    /// The literal is well-formed, but its value does not fit in an `i64` or
+    /// a finite `f64`
+    Overflow,
+}
+
+/// Parses a numeric literal, as returned by the tokenizer.
+///
+/// The grammar is:
+/// - an optional `+` or `-` sign, followed by
+/// - `inf` or `nan`, or
+/// - a `0x`, `0o` or `0b` prefix and an integer in that base, or
+/// - decimal digits, an optional fraction after a `.`, and an optional exponent
+///   after an `e` or `E`. The literal is a float if it has a fraction or an
+///   exponent, and an integer otherwise.
+///
+/// Digits may be separated by single `_` characters.
+///
+/// `inf` and `nan` are only numbers as values: as dictionary keys, they are
+/// words like any other.
+pub(crate) fn parse_number(literal: &str) -> Result<Number, NumberError> {
+    let (negative, unsigned) = match literal.as_bytes().first() {
+        Some(b'-') => (true, &literal[1..]),
+        Some(b'+') => (false, &literal[1..]),
+        _ => (false, literal),
+    };
+
+    match unsigned {
+        "inf" => {
+            return Ok(Number::Float(if negative {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            }))
+        }
+        "nan" => return Ok(Number::Float(f64::NAN)),
+        _ => {}
+    }
+
+    let radix = match unsigned.get(..2) {
+        Some("0x") => Some(16),
+        Some("0o") => Some(8),
+        Some("0b") => Some(2),
+        _ => None,
+    };
+
+    if let Some(radix) = radix {
+        let digits = strip_separators(&unsigned[2..], |c| c.is_digit(radix))?;
+        let magnitude = u64::from_str_radix(&digits, radix).map_err(|_| NumberError::Overflow)?;
+        return integer(negative, magnitude);
+    }
+
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(i) => (&unsigned[..i], Some(&unsigned[i + 1..])),
+        None => (unsigned, None),
+    };
+
+    let (whole, fraction) = match mantissa.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (mantissa, None),
+    };
+
+    let mut normalized = strip_separators(whole, |c| c.is_ascii_digit())?;
+
+    // A trailing `.` with no fraction digits, as in `11.`, is still a float
+    if let Some(fraction) = fraction.filter(|f| !f.is_empty()) {
+        normalized.push('.');
+        normalized.push_str(&strip_separators(fraction, |c| c.is_ascii_digit())?);
+    }
+
+    if let Some(exponent) = exponent {
+        let (sign, digits) = match exponent.as_bytes().first() {
+            Some(b'-') => ("-", &exponent[1..]),
+            Some(b'+') => ("", &exponent[1..]),
+            _ => ("", exponent),
+        };
+        normalized.push('e');
+        normalized.push_str(sign);
+        normalized.push_str(&strip_separators(digits, |c| c.is_ascii_digit())?);
+    }
+
+    if fraction.is_none() && exponent.is_none() {
+        let magnitude = normalized
+            .parse::<u64>()
+            .map_err(|_| NumberError::Overflow)?;
+        return integer(negative, magnitude);
+    }
+
+    let f = normalized
+        .parse::<f64>()
+        .map_err(|_| NumberError::Invalid)?;
+
+    if f.is_infinite() {
+        return Err(NumberError::Overflow);
+    }
+
+    Ok(Number::Float(if negative { -f } else { f }))
+}
+
+/// Parses a numeric literal into a `Value`, failing with
+/// `Error::InvalidNumber` or `Error::NumberOverflow` at `location`.
+pub(crate) fn number_value(literal: &str, location: Location) -> crate::Result<OwnedValue> {
+    match parse_number(literal) {
+        Ok(Number::Int(i)) => Ok(Value::Int(i)),
+        Ok(Number::Float(f)) => Ok(Value::Float(f)),
+        Err(NumberError::Invalid) => Err(Error::InvalidNumber {
+            literal: literal.to_string(),
+            location,
+        }),
+        Err(NumberError::Overflow) => Err(Error::NumberOverflow {
+            literal: literal.to_string(),
+            location,
+        }),
+    }
+}
+
+/// Removes `_` separators from a run of digits, checking that it is not empty,
+/// that every other character is a digit and that every separator sits between
+/// two digits.
+fn strip_separators(digits: &str, is_digit: impl Fn(char) -> bool) -> Result<String, NumberError> {
+    if digits.is_empty()
+        || digits.starts_with('_')
+        || digits.ends_with('_')
+        || digits.contains("__")
+    {
+        return Err(NumberError::Invalid);
+    }
+
+    let mut stripped = String::with_capacity(digits.len());
+    for c in digits.chars() {
+        if is_digit(c) {
+            stripped.push(c);
+        } else if c != '_' {
+            return Err(NumberError::Invalid);
+        }
+    }
+    Ok(stripped)
+}
+
+fn integer(negative: bool, magnitude: u64) -> Result<Number, NumberError> {
+    if negative {
+        0i64.checked_sub_unsigned(magnitude)
+            .map(Number::Int)
+            .ok_or(NumberError::Overflow)
+    } else {
+        i64::try_from(magnitude)
+            .map(Number::Int)
+            .map_err(|_| NumberError::Overflow)
+    }
+}
@@ -0,0 +1,520 @@
+//! Selecting values from a `Value` with path queries, and transforming them
+//! with pipelines.
+//!
+//! A query is a sequence of segments, each selecting values from the results
+//! of the one before it, starting from the queried value:
+//! - `.key` or `."quoted key"` selects the value of a dictionary key,
+//! - `.*`, `[*]` or `[]` selects every element of a list or value of a dictionary,
+//! - `[n]` selects a list element, counting from the end if `n` is negative,
+//! - `[start:end]` selects the list elements from `start` up to `end`, either
+//!   of which can be left out or negative,
+//! - `..key`, `..*` or `..[...]` applies the selector to the value and all of
+//!   its descendants, so `..name` finds every `name` key at any depth,
+//! - `[?(filter)]` selects the elements of a list or values of a dictionary
+//!   the filter matches.
+//!
+//! A filter compares the values of relative queries, starting with `.` or
+//! `@` (the value being filtered), with each other or with literals, using
+//! `==`, `!=`, `<`, `<=`, `>` and `>=`. A query alone matches if it selects a
+//! value other than `none` or `false`. Filters can be combined with `&&`,
+//! `||`, `!` and parentheses.
+//!
+//! The query `.` selects the value itself. For convenience, a leading `.` can be
+//! left out, as in `characters[0]`.
+//!
+//! Queries only select values that are already there. To compute new values,
+//! such as counts, sorted lists or dictionaries with a few of the keys, use a
+//! [`Pipeline`].
+//!
+//! # Examples
+//! ```
+//! use dent_parse::{query::Query, Dent, Value};
+//!
+//! let value = Dent::default()
+//!     .parse("{ characters: [ { name: Mario age: 35 } { name: Luigi age: 30 } ] }")
+//!     .unwrap();
+//!
+//! let query = Query::parse(".characters[?(.age > 31)].name").unwrap();
+//! assert_eq!(query.select(&value), vec![&Value::Str("Mario".into())]);
+//!
+//! let query = Query::parse("..name").unwrap();
+//! assert_eq!(query.select(&value).len(), 2);
+//! ```
+
+mod pipeline;
+
+use std::cmp::Ordering;
+
+use crate::{
+    number::number_value,
+    tokenizer::{Token, Tokenizer},
+    Error, Location, OwnedValue, Result, Span, Value,
+};
+
+pub use pipeline::Pipeline;
+
+/// A parsed query.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Query {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Segment {
+    Child(Selector),
+    Descendant(Selector),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Selector {
+    Key(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Filter(Filter),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Filter {
+    Truthy(Query),
+    Compare(Operand, Comparison, Operand),
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Operand {
+    Query(Query),
+    Literal(OwnedValue),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Query {
+    /// Parses a query.
+    ///
+    /// Fails with `Error::InvalidQuery`, located in the query string, if the
+    /// query is not valid.
+    pub fn parse(query: &str) -> Result<Query> {
+        let mut parser = QueryParser {
+            input: query,
+            pos: 0,
+        };
+        parser.skip_whitespace();
+
+        // A leading key can be written without a `.`
+        let key = match parser.peek() {
+            Some(c) if is_key_char(c) => Some(parser.key()),
+            _ => None,
+        };
+
+        let mut result = parser.query()?;
+        if let Some(key) = key {
+            result
+                .segments
+                .insert(0, Segment::Child(Selector::Key(key)));
+        }
+
+        parser.skip_whitespace();
+        if parser.pos < query.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(result)
+    }
+
+    /// Returns the values the query selects from a value, in document order.
+    pub fn select<'v, 's>(&self, value: &'v Value<'s>) -> Vec<&'v Value<'s>> {
+        let mut current = vec![value];
+        for segment in &self.segments {
+            let mut next = Vec::new();
+            for value in current {
+                match segment {
+                    Segment::Child(selector) => selector.select(value, &mut next),
+                    Segment::Descendant(selector) => {
+                        let mut descendants = Vec::new();
+                        descendants_and_self(value, &mut descendants);
+                        for value in descendants {
+                            selector.select(value, &mut next);
+                        }
+                    }
+                }
+            }
+            current = next;
+        }
+        current
+    }
+}
+
+fn descendants_and_self<'v, 's>(value: &'v Value<'s>, out: &mut Vec<&'v Value<'s>>) {
+    out.push(value);
+    for child in children(value) {
+        descendants_and_self(child, out);
+    }
+}
+
+/// Returns the elements of a list or values of a dictionary.
+fn children<'v, 's>(value: &'v Value<'s>) -> Box<dyn Iterator<Item = &'v Value<'s>> + 'v> {
+    match value {
+        Value::List(l) => Box::new(l.iter()),
+        Value::Dict(d) => Box::new(d.values()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+/// Resolves a possibly negative index into a list of length `len`, without
+/// checking that it is in bounds.
+fn resolve_index(index: i64, len: usize) -> i64 {
+    if index < 0 {
+        len as i64 + index
+    } else {
+        index
+    }
+}
+
+impl Selector {
+    fn select<'v, 's>(&self, value: &'v Value<'s>, out: &mut Vec<&'v Value<'s>>) {
+        match self {
+            Selector::Key(key) => {
+                if let Some(v) = value.as_dict().and_then(|d| d.get(key.as_str())) {
+                    out.push(v);
+                }
+            }
+            Selector::Wildcard => out.extend(children(value)),
+            Selector::Index(index) => {
+                if let Some(l) = value.as_list() {
+                    let index = resolve_index(*index, l.len());
+                    if let Some(v) = usize::try_from(index).ok().and_then(|i| l.get(i)) {
+                        out.push(v);
+                    }
+                }
+            }
+            Selector::Slice(start, end) => {
+                if let Some(l) = value.as_list() {
+                    let clamp =
+                        |i: i64| resolve_index(i, l.len()).clamp(0, l.len() as i64) as usize;
+                    let start = start.map_or(0, clamp);
+                    let end = end.map_or(l.len(), clamp);
+                    if start < end {
+                        out.extend(&l[start..end]);
+                    }
+                }
+            }
+            Selector::Filter(filter) => {
+                out.extend(children(value).filter(|v| filter.matches(v)));
+            }
+        }
+    }
+}
+
+impl Filter {
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            Filter::Truthy(query) => query
+                .select(value)
+                .first()
+                .is_some_and(|v| !matches!(v, Value::None | Value::Bool(false))),
+            Filter::Compare(left, comparison, right) => {
+                comparison.test(&left.value(value), &right.value(value))
+            }
+            Filter::Not(filter) => !filter.matches(value),
+            Filter::And(a, b) => a.matches(value) && b.matches(value),
+            Filter::Or(a, b) => a.matches(value) || b.matches(value),
+        }
+    }
+}
+
+impl Operand {
+    /// Returns the value of the operand for a filtered value. A query that
+    /// selects nothing has the value `none`.
+    fn value<'v, 's>(&'v self, value: &'v Value<'s>) -> Value<'v> {
+        match self {
+            Operand::Query(query) => query
+                .select(value)
+                .first()
+                .map_or(Value::None, |v| v.as_borrowed()),
+            Operand::Literal(literal) => literal.as_borrowed(),
+        }
+    }
+}
+
+impl Comparison {
+    fn test(self, left: &Value, right: &Value) -> bool {
+        match self {
+            Comparison::Eq => equal(left, right),
+            Comparison::Ne => !equal(left, right),
+            Comparison::Lt => compare(left, right) == Some(Ordering::Less),
+            Comparison::Le => {
+                matches!(compare(left, right), Some(Ordering::Less | Ordering::Equal))
+            }
+            Comparison::Gt => compare(left, right) == Some(Ordering::Greater),
+            Comparison::Ge => matches!(
+                compare(left, right),
+                Some(Ordering::Greater | Ordering::Equal)
+            ),
+        }
+    }
+}
+
+/// Compares two values for equality, treating integers and floats with the
+/// same value as equal.
+fn equal(a: &Value, b: &Value) -> bool {
+    compare(a, b) == Some(Ordering::Equal) || a == b
+}
+
+/// Orders two numbers or two strings. Other values are not ordered.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Int(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+        (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f64)),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+struct QueryParser<'q> {
+    input: &'q str,
+    pos: usize,
+}
+
+impl<'q> QueryParser<'q> {
+    fn rest(&self) -> &'q str {
+        &self.input[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consumes `s` if the rest of the query starts with it.
+    fn eat(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, s: &str) -> Result<()> {
+        self.skip_whitespace();
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{}`", s)))
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        let len = self.peek().map_or(0, |c| c.len_utf8());
+        let column = self.input[..self.pos].chars().count() + 1;
+        Error::InvalidQuery {
+            message: message.to_string(),
+            location: Location::new(Span::new(self.pos, self.pos + len, 1, column)),
+        }
+    }
+
+    /// Parses segments until the next character cannot start one.
+    fn query(&mut self) -> Result<Query> {
+        let mut segments = Vec::new();
+        loop {
+            if self.eat("..") {
+                segments.push(Segment::Descendant(self.dot_selector()?));
+            } else if self.eat(".") {
+                if self
+                    .peek()
+                    .is_some_and(|c| is_key_char(c) || c == '*' || c == '"' || c == '[')
+                {
+                    segments.push(Segment::Child(self.dot_selector()?));
+                } else if !segments.is_empty() {
+                    return Err(self.error("expected a key after `.`"));
+                }
+            } else if self.peek() == Some('[') {
+                segments.push(Segment::Child(self.bracket()?));
+            } else {
+                return Ok(Query { segments });
+            }
+        }
+    }
+
+    /// Parses the selector after a `.` or `..`.
+    fn dot_selector(&mut self) -> Result<Selector> {
+        match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('[') => self.bracket(),
+            Some('"') => Ok(Selector::Key(self.string()?)),
+            Some(c) if is_key_char(c) => Ok(Selector::Key(self.key())),
+            _ => Err(self.error("expected a key, `*` or `[`")),
+        }
+    }
+
+    fn key(&mut self) -> String {
+        let rest = self.rest();
+        let end = rest.find(|c| !is_key_char(c)).unwrap_or(rest.len());
+        self.pos += end;
+        rest[..end].to_string()
+    }
+
+    fn string(&mut self) -> Result<String> {
+        match self.literal()? {
+            Value::Str(s) => Ok(s.into_owned()),
+            _ => Err(self.error("expected a string")),
+        }
+    }
+
+    /// Parses a string, number, boolean or `none` with the Dent tokenizer.
+    fn literal(&mut self) -> Result<OwnedValue> {
+        let mut tokenizer = Tokenizer::new(self.rest());
+        let token = tokenizer
+            .next()
+            .map_err(|_| self.error("invalid literal"))?;
+
+        let value = match token {
+            Token::String(s) if self.rest().starts_with('"') => Value::from(s.into_owned()),
+            Token::Number(n) => number_value(n, Location::new(tokenizer.span()))
+                .map_err(|_| self.error("invalid number"))?,
+            Token::Bool(b) => Value::Bool(b),
+            Token::None => Value::None,
+            _ => return Err(self.error("expected a string, number, boolean or `none`")),
+        };
+        self.pos += tokenizer.span().end;
+        Ok(value)
+    }
+
+    fn integer(&mut self) -> Result<Option<i64>> {
+        self.skip_whitespace();
+        if !self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || c == '-' || c == '+')
+        {
+            return Ok(None);
+        }
+        match self.literal()? {
+            Value::Int(i) => Ok(Some(i)),
+            _ => Err(self.error("expected an integer")),
+        }
+    }
+
+    /// Parses a `[...]` selector.
+    fn bracket(&mut self) -> Result<Selector> {
+        self.expect("[")?;
+        self.skip_whitespace();
+
+        let selector = if self.eat("*") || self.rest().starts_with(']') {
+            Selector::Wildcard
+        } else if self.peek() == Some('"') {
+            Selector::Key(self.string()?)
+        } else if self.eat("?") {
+            self.expect("(")?;
+            let filter = self.or()?;
+            self.expect(")")?;
+            Selector::Filter(filter)
+        } else {
+            let start = self.integer()?;
+            self.skip_whitespace();
+            if self.eat(":") {
+                Selector::Slice(start, self.integer()?)
+            } else if let Some(index) = start {
+                Selector::Index(index)
+            } else {
+                return Err(self.error("expected an index, slice, key, `*` or filter"));
+            }
+        };
+
+        self.expect("]")?;
+        Ok(selector)
+    }
+
+    fn or(&mut self) -> Result<Filter> {
+        let mut filter = self.and()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("||") {
+                return Ok(filter);
+            }
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+    }
+
+    fn and(&mut self) -> Result<Filter> {
+        let mut filter = self.unary()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("&&") {
+                return Ok(filter);
+            }
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Filter> {
+        self.skip_whitespace();
+        if self.rest().starts_with('!') && !self.rest().starts_with("!=") {
+            self.pos += 1;
+            return Ok(Filter::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let filter = self.or()?;
+            self.expect(")")?;
+            return Ok(filter);
+        }
+
+        let left = self.operand()?;
+        self.skip_whitespace();
+
+        match (self.comparison(), left) {
+            (Some(comparison), left) => {
+                self.skip_whitespace();
+                Ok(Filter::Compare(left, comparison, self.operand()?))
+            }
+            (None, Operand::Query(query)) => Ok(Filter::Truthy(query)),
+            (None, Operand::Literal(_)) => Err(self.error("expected a comparison")),
+        }
+    }
+
+    /// Consumes a comparison operator, if the rest of the query starts with one.
+    fn comparison(&mut self) -> Option<Comparison> {
+        [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ]
+        .into_iter()
+        .find(|(op, _)| self.eat(op))
+        .map(|(_, comparison)| comparison)
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        self.skip_whitespace();
+        if self.eat("@") || self.peek() == Some('.') {
+            Ok(Operand::Query(self.query()?))
+        } else {
+            Ok(Operand::Literal(self.literal()?))
+        }
+    }
+}
@@ -0,0 +1,793 @@
+use std::cmp::Ordering;
+
+use indexmap::IndexMap;
+
+use super::{compare, equal, is_key_char, Comparison, Query, QueryParser, Segment, Selector};
+use crate::{Error, Location, OwnedValue, Result, Span, Value};
+
+/// A parsed pipeline, which transforms a value into any number of new values.
+///
+/// Every expression in a pipeline takes an input value and produces zero or
+/// more output values:
+/// - a path query, such as `.characters[0].name`, produces the values it
+///   selects from the input, which is nothing for a missing key,
+/// - a string, number, boolean or `none` produces itself,
+/// - `a | b` runs `b` on each output of `a`,
+/// - `a, b` produces the outputs of `a` followed by those of `b`,
+/// - `[a]` collects the outputs of `a` into a list,
+/// - `{ name: a, age: b }` builds a dictionary, where `{ name }` is short
+///   for `{ name: .name }`,
+/// - `+`, `-`, `*`, `/` and `%` do arithmetic on numbers, and `+` also joins
+///   strings and lists and merges dictionaries,
+/// - `==`, `!=`, `<`, `<=`, `>` and `>=` compare values as in query filters,
+///   `and` and `or` combine them, and a leading `!` negates an expression,
+/// - `(a)` groups an expression, and can be followed by a path, as in
+///   `(.a | .b)[0]`.
+///
+/// The functions `length`, `keys`, `values`, `map(f)`, `select(f)`, `sort`,
+/// `sort_by(f)`, `group_by(f)`, `unique`, `reverse`, `first`, `last`,
+/// `add`, `min`, `max`, `has(key)`, `type` and `not` work like their jq
+/// counterparts, except that `keys` keeps the order of the dictionary. As in
+/// jq, `not` is a function rather than an operator, so it is used as
+/// `.a | not`.
+///
+/// As in queries, a leading `.` can be left out, unless the key is the name
+/// of a function.
+///
+/// # Examples
+/// ```
+/// use dent_parse::{query::Pipeline, Dent, Value};
+///
+/// let value = Dent::default()
+///     .parse("{ characters: [ { name: Mario age: 35 } { name: Luigi age: 30 } ] }")
+///     .unwrap();
+///
+/// let pipeline = Pipeline::parse(".characters | sort_by(.age) | map(.name)").unwrap();
+/// assert_eq!(
+///     pipeline.run(&value).unwrap(),
+///     vec![Value::List(vec!["Luigi".into(), "Mario".into()])]
+/// );
+///
+/// let pipeline = Pipeline::parse(".characters[] | { name, older: .age + 10 }").unwrap();
+/// assert_eq!(pipeline.run(&value).unwrap().len(), 2);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct Pipeline {
+    expr: Expr,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Expr {
+    Path(Query),
+    Literal(OwnedValue),
+    /// A path applied to the outputs of another expression
+    Suffix(Box<Expr>, Query),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Neg(Box<Expr>, Span),
+    Binary(Box<Expr>, Operator, Box<Expr>, Span),
+    List(Option<Box<Expr>>),
+    Dict(Vec<(String, Expr)>),
+    Call(Builtin, Vec<Expr>, Span),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Compare(Comparison),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Builtin {
+    Length,
+    Keys,
+    Values,
+    Map,
+    Select,
+    Sort,
+    SortBy,
+    GroupBy,
+    Unique,
+    Reverse,
+    First,
+    Last,
+    Add,
+    Min,
+    Max,
+    Not,
+    Has,
+    Type,
+}
+
+/// The name and number of arguments of every function.
+const BUILTINS: &[(&str, Builtin, usize)] = &[
+    ("length", Builtin::Length, 0),
+    ("keys", Builtin::Keys, 0),
+    ("values", Builtin::Values, 0),
+    ("map", Builtin::Map, 1),
+    ("select", Builtin::Select, 1),
+    ("sort", Builtin::Sort, 0),
+    ("sort_by", Builtin::SortBy, 1),
+    ("group_by", Builtin::GroupBy, 1),
+    ("unique", Builtin::Unique, 0),
+    ("reverse", Builtin::Reverse, 0),
+    ("first", Builtin::First, 0),
+    ("last", Builtin::Last, 0),
+    ("add", Builtin::Add, 0),
+    ("min", Builtin::Min, 0),
+    ("max", Builtin::Max, 0),
+    ("not", Builtin::Not, 0),
+    ("has", Builtin::Has, 1),
+    ("type", Builtin::Type, 0),
+];
+
+impl Pipeline {
+    /// Parses a pipeline.
+    ///
+    /// Fails with `Error::InvalidQuery`, located in the pipeline string, if
+    /// the pipeline is not valid.
+    pub fn parse(pipeline: &str) -> Result<Pipeline> {
+        let mut parser = QueryParser {
+            input: pipeline,
+            pos: 0,
+        };
+        let expr = parser.pipe()?;
+
+        parser.skip_whitespace();
+        if parser.pos < pipeline.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(Pipeline { expr })
+    }
+
+    /// Runs the pipeline on a value, returning its outputs in order.
+    ///
+    /// Fails with `Error::QueryFailed`, located at the operator or function
+    /// in the pipeline string, if an operation cannot be applied to its
+    /// operands, such as adding a string to a number or sorting a dictionary.
+    pub fn run(&self, value: &Value) -> Result<Vec<OwnedValue>> {
+        self.expr.eval(value)
+    }
+}
+
+impl Expr {
+    fn eval(&self, input: &Value) -> Result<Vec<OwnedValue>> {
+        let mut out = Vec::new();
+        match self {
+            Expr::Path(query) => {
+                out.extend(query.select(input).into_iter().map(Value::to_owned_value));
+            }
+            Expr::Literal(value) => out.push(value.clone()),
+            Expr::Suffix(expr, query) => {
+                for value in expr.eval(input)? {
+                    out.extend(query.select(&value).into_iter().cloned());
+                }
+            }
+            Expr::Pipe(a, b) => {
+                for value in a.eval(input)? {
+                    out.extend(b.eval(&value)?);
+                }
+            }
+            Expr::Comma(a, b) => {
+                out.extend(a.eval(input)?);
+                out.extend(b.eval(input)?);
+            }
+            Expr::Or(a, b) => {
+                for value in a.eval(input)? {
+                    if truthy(&value) {
+                        out.push(Value::Bool(true));
+                    } else {
+                        out.extend(b.eval(input)?.iter().map(|v| Value::Bool(truthy(v))));
+                    }
+                }
+            }
+            Expr::And(a, b) => {
+                for value in a.eval(input)? {
+                    if truthy(&value) {
+                        out.extend(b.eval(input)?.iter().map(|v| Value::Bool(truthy(v))));
+                    } else {
+                        out.push(Value::Bool(false));
+                    }
+                }
+            }
+            Expr::Not(expr) => {
+                out.extend(expr.eval(input)?.iter().map(|v| Value::Bool(!truthy(v))));
+            }
+            Expr::Neg(expr, span) => {
+                for value in expr.eval(input)? {
+                    out.push(match value {
+                        Value::Int(i) => Value::Int(
+                            i.checked_neg()
+                                .ok_or_else(|| query_failed("integer overflow", span))?,
+                        ),
+                        Value::Float(f) => Value::Float(-f),
+                        v => {
+                            let message = format!("cannot negate {}", v.type_name());
+                            return Err(query_failed(&message, span));
+                        }
+                    });
+                }
+            }
+            Expr::Binary(a, operator, b, span) => {
+                let right = b.eval(input)?;
+                for left in a.eval(input)? {
+                    for right in &right {
+                        out.push(
+                            operator
+                                .apply(&left, right)
+                                .map_err(|message| query_failed(&message, span))?,
+                        );
+                    }
+                }
+            }
+            Expr::List(None) => out.push(Value::List(Vec::new())),
+            Expr::List(Some(expr)) => out.push(Value::List(expr.eval(input)?)),
+            Expr::Dict(entries) => {
+                // Every combination of the outputs of the values makes a dictionary
+                let mut dicts = vec![IndexMap::new()];
+                for (key, expr) in entries {
+                    let values = expr.eval(input)?;
+                    let mut next = Vec::new();
+                    for dict in &dicts {
+                        for value in &values {
+                            let mut dict = dict.clone();
+                            dict.insert(key.clone().into(), value.clone());
+                            next.push(dict);
+                        }
+                    }
+                    dicts = next;
+                }
+                out.extend(dicts.into_iter().map(Value::Dict));
+            }
+            Expr::Call(builtin, args, span) => return builtin.call(input, args, span),
+        }
+        Ok(out)
+    }
+}
+
+impl Operator {
+    fn symbol(self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Sub => "-",
+            Operator::Mul => "*",
+            Operator::Div => "/",
+            Operator::Rem => "%",
+            Operator::Compare(Comparison::Eq) => "==",
+            Operator::Compare(Comparison::Ne) => "!=",
+            Operator::Compare(Comparison::Lt) => "<",
+            Operator::Compare(Comparison::Le) => "<=",
+            Operator::Compare(Comparison::Gt) => ">",
+            Operator::Compare(Comparison::Ge) => ">=",
+        }
+    }
+
+    fn apply(
+        self,
+        left: &OwnedValue,
+        right: &OwnedValue,
+    ) -> std::result::Result<OwnedValue, String> {
+        let value = match (self, left, right) {
+            (Operator::Compare(comparison), left, right) => {
+                Value::Bool(comparison.test(left, right))
+            }
+            (Operator::Add, Value::None, v) | (Operator::Add, v, Value::None) => v.clone(),
+            (_, Value::Int(a), Value::Int(b)) => self.apply_int(*a, *b)?,
+            (_, Value::Int(a), Value::Float(b)) => self.apply_float(*a as f64, *b)?,
+            (_, Value::Float(a), Value::Int(b)) => self.apply_float(*a, *b as f64)?,
+            (_, Value::Float(a), Value::Float(b)) => self.apply_float(*a, *b)?,
+            (Operator::Add, Value::Str(a), Value::Str(b)) => Value::from(format!("{}{}", a, b)),
+            (Operator::Add, Value::List(a), Value::List(b)) => {
+                Value::List(a.iter().chain(b).cloned().collect())
+            }
+            (Operator::Add, Value::Dict(a), Value::Dict(b)) => {
+                let mut dict = a.clone();
+                dict.extend(b.iter().map(|(k, v)| (k.clone(), v.clone())));
+                Value::Dict(dict)
+            }
+            (Operator::Sub, Value::List(a), Value::List(b)) => Value::List(
+                a.iter()
+                    .filter(|v| !b.iter().any(|w| equal(v, w)))
+                    .cloned()
+                    .collect(),
+            ),
+            _ => {
+                return Err(format!(
+                    "cannot apply `{}` to {} and {}",
+                    self.symbol(),
+                    left.type_name(),
+                    right.type_name()
+                ))
+            }
+        };
+        Ok(value)
+    }
+
+    fn apply_int(self, a: i64, b: i64) -> std::result::Result<OwnedValue, String> {
+        if matches!(self, Operator::Div | Operator::Rem) && b == 0 {
+            return Err("division by zero".to_string());
+        }
+        let result = match self {
+            Operator::Add => a.checked_add(b),
+            Operator::Sub => a.checked_sub(b),
+            Operator::Mul => a.checked_mul(b),
+            // Division only gives an integer if there is no remainder. The
+            // remainder of `i64::MIN / -1` overflows, as does the quotient
+            Operator::Div if matches!(a.checked_rem(b), Some(r) if r != 0) => {
+                return Ok(Value::Float(a as f64 / b as f64))
+            }
+            Operator::Div => a.checked_div(b),
+            Operator::Rem => a.checked_rem(b),
+            Operator::Compare(_) => unreachable!(),
+        };
+        result
+            .map(Value::Int)
+            .ok_or_else(|| "integer overflow".to_string())
+    }
+
+    fn apply_float(self, a: f64, b: f64) -> std::result::Result<OwnedValue, String> {
+        if matches!(self, Operator::Div | Operator::Rem) && b == 0.0 {
+            return Err("division by zero".to_string());
+        }
+        Ok(Value::Float(match self {
+            Operator::Add => a + b,
+            Operator::Sub => a - b,
+            Operator::Mul => a * b,
+            Operator::Div => a / b,
+            Operator::Rem => a % b,
+            Operator::Compare(_) => unreachable!(),
+        }))
+    }
+}
+
+impl Builtin {
+    fn name(self) -> &'static str {
+        BUILTINS
+            .iter()
+            .find(|(_, builtin, _)| *builtin == self)
+            .map_or("", |(name, _, _)| name)
+    }
+
+    fn call(self, input: &Value, args: &[Expr], span: &Span) -> Result<Vec<OwnedValue>> {
+        let unsupported = || {
+            let message = format!("cannot apply `{}` to {}", self.name(), input.type_name());
+            query_failed(&message, span)
+        };
+        let list = || input.as_list().ok_or_else(unsupported);
+
+        let value = match self {
+            Builtin::Length => match input {
+                Value::None => Value::Int(0),
+                Value::Str(s) => Value::Int(s.chars().count() as i64),
+                Value::List(l) => Value::Int(l.len() as i64),
+                Value::Dict(d) => Value::Int(d.len() as i64),
+                _ => return Err(unsupported()),
+            },
+            Builtin::Keys => match input {
+                Value::List(l) => Value::List((0..l.len() as i64).map(Value::Int).collect()),
+                Value::Dict(d) => {
+                    Value::List(d.keys().map(|k| Value::from(k.to_string())).collect())
+                }
+                _ => return Err(unsupported()),
+            },
+            Builtin::Values => match input {
+                Value::List(l) => Value::List(l.iter().map(Value::to_owned_value).collect()),
+                Value::Dict(d) => Value::List(d.values().map(Value::to_owned_value).collect()),
+                _ => return Err(unsupported()),
+            },
+            Builtin::Map => {
+                let items: Vec<_> = match input {
+                    Value::List(l) => l.iter().collect(),
+                    Value::Dict(d) => d.values().collect(),
+                    _ => return Err(unsupported()),
+                };
+                let mut out = Vec::new();
+                for item in items {
+                    out.extend(args[0].eval(item)?);
+                }
+                Value::List(out)
+            }
+            Builtin::Select => {
+                let selected = args[0].eval(input)?.iter().any(truthy);
+                return Ok(if selected {
+                    vec![input.to_owned_value()]
+                } else {
+                    Vec::new()
+                });
+            }
+            Builtin::Sort => {
+                let mut items: Vec<_> = list()?.iter().map(Value::to_owned_value).collect();
+                items.sort_by(order);
+                Value::List(items)
+            }
+            Builtin::SortBy => Value::List(
+                sort_by_key(list()?, &args[0])?
+                    .into_iter()
+                    .map(|(_, v)| v)
+                    .collect(),
+            ),
+            Builtin::GroupBy => {
+                let mut groups: Vec<(OwnedValue, Vec<OwnedValue>)> = Vec::new();
+                for (key, item) in sort_by_key(list()?, &args[0])? {
+                    match groups.last_mut() {
+                        Some((last, group)) if order(last, &key) == Ordering::Equal => {
+                            group.push(item)
+                        }
+                        _ => groups.push((key, vec![item])),
+                    }
+                }
+                Value::List(groups.into_iter().map(|(_, g)| Value::List(g)).collect())
+            }
+            Builtin::Unique => {
+                let mut items: Vec<_> = list()?.iter().map(Value::to_owned_value).collect();
+                items.sort_by(order);
+                items.dedup_by(|a, b| order(a, b) == Ordering::Equal);
+                Value::List(items)
+            }
+            Builtin::Reverse => match input {
+                Value::None => Value::None,
+                Value::Str(s) => Value::from(s.chars().rev().collect::<String>()),
+                Value::List(l) => Value::List(l.iter().rev().map(Value::to_owned_value).collect()),
+                _ => return Err(unsupported()),
+            },
+            Builtin::First => list()?.first().map_or(Value::None, Value::to_owned_value),
+            Builtin::Last => list()?.last().map_or(Value::None, Value::to_owned_value),
+            Builtin::Add => {
+                let mut sum = Value::None;
+                for item in list()? {
+                    sum = Operator::Add
+                        .apply(&sum, &item.to_owned_value())
+                        .map_err(|message| query_failed(&message, span))?;
+                }
+                sum
+            }
+            Builtin::Min => list()?
+                .iter()
+                .min_by(|a, b| order(a, b))
+                .map_or(Value::None, Value::to_owned_value),
+            Builtin::Max => list()?
+                .iter()
+                .max_by(|a, b| order(a, b))
+                .map_or(Value::None, Value::to_owned_value),
+            Builtin::Not => Value::Bool(!truthy(input)),
+            Builtin::Has => {
+                let mut out = Vec::new();
+                for key in args[0].eval(input)? {
+                    out.push(Value::Bool(match (input, &key) {
+                        (Value::Dict(d), Value::Str(k)) => d.contains_key(k.as_ref()),
+                        (Value::List(l), Value::Int(i)) => (0..l.len() as i64).contains(i),
+                        _ => {
+                            let message = format!(
+                                "cannot check whether {} has a {} key",
+                                input.type_name(),
+                                key.type_name()
+                            );
+                            return Err(query_failed(&message, span));
+                        }
+                    }));
+                }
+                return Ok(out);
+            }
+            Builtin::Type => Value::from(input.type_name()),
+        };
+        Ok(vec![value])
+    }
+}
+
+/// Sorts the elements of a list by the outputs of `key` for each of them,
+/// keeping the original order of elements with equal keys.
+fn sort_by_key(items: &[Value], key: &Expr) -> Result<Vec<(OwnedValue, OwnedValue)>> {
+    let mut keyed = Vec::new();
+    for item in items {
+        keyed.push((Value::List(key.eval(item)?), item.to_owned_value()));
+    }
+    keyed.sort_by(|a, b| order(&a.0, &b.0));
+    Ok(keyed)
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::None | Value::Bool(false))
+}
+
+/// Orders any two values, for sorting: `none` comes first, followed by
+/// booleans, numbers, strings, lists and dictionaries.
+fn order(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::None => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Int(_) | Value::Float(_) => 3,
+            Value::Str(_) => 4,
+            Value::List(_) => 5,
+            Value::Dict(_) => 6,
+        }
+    }
+
+    match (a, b) {
+        (Value::List(a), Value::List(b)) => {
+            order_all(a.iter().zip(b.iter())).then_with(|| a.len().cmp(&b.len()))
+        }
+        (Value::Dict(a), Value::Dict(b)) => {
+            // Dictionaries are ordered by their sorted keys, then by the
+            // values of those keys
+            let mut a: Vec<_> = a.iter().collect();
+            let mut b: Vec<_> = b.iter().collect();
+            a.sort_by(|x, y| x.0.cmp(y.0));
+            b.sort_by(|x, y| x.0.cmp(y.0));
+            a.iter()
+                .map(|e| e.0)
+                .cmp(b.iter().map(|e| e.0))
+                .then_with(|| order_all(a.iter().zip(&b).map(|(x, y)| (x.1, y.1))))
+        }
+        _ => rank(a)
+            .cmp(&rank(b))
+            .then_with(|| compare(a, b).unwrap_or(Ordering::Equal)),
+    }
+}
+
+/// Returns the order of the first pair of values that are not equal.
+fn order_all<'v, 's: 'v>(pairs: impl Iterator<Item = (&'v Value<'s>, &'v Value<'s>)>) -> Ordering {
+    pairs
+        .map(|(a, b)| order(a, b))
+        .find(|o| o.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+fn query_failed(message: &str, span: &Span) -> Error {
+    Error::QueryFailed {
+        message: message.to_string(),
+        location: Location::new(*span),
+    }
+}
+
+impl QueryParser<'_> {
+    /// Returns the span from `start` to the current position.
+    fn span(&self, start: usize) -> Span {
+        let column = self.input[..start].chars().count() + 1;
+        Span::new(start, self.pos, 1, column)
+    }
+
+    /// Consumes a word, if the rest of the pipeline starts with it and it is
+    /// not the start of a longer key.
+    fn eat_word(&mut self, word: &str) -> bool {
+        let rest = self.rest();
+        if rest.starts_with(word) && !rest[word.len()..].starts_with(is_key_char) {
+            self.pos += word.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn pipe(&mut self) -> Result<Expr> {
+        let mut expr = self.comma()?;
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("||") || !self.eat("|") {
+                return Ok(expr);
+            }
+            expr = Expr::Pipe(Box::new(expr), Box::new(self.comma()?));
+        }
+    }
+
+    fn comma(&mut self) -> Result<Expr> {
+        let mut expr = self.disjunction()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat(",") {
+                return Ok(expr);
+            }
+            expr = Expr::Comma(Box::new(expr), Box::new(self.disjunction()?));
+        }
+    }
+
+    fn disjunction(&mut self) -> Result<Expr> {
+        let mut expr = self.conjunction()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("||") && !self.eat_word("or") {
+                return Ok(expr);
+            }
+            expr = Expr::Or(Box::new(expr), Box::new(self.conjunction()?));
+        }
+    }
+
+    fn conjunction(&mut self) -> Result<Expr> {
+        let mut expr = self.comparison_expr()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat("&&") && !self.eat_word("and") {
+                return Ok(expr);
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.comparison_expr()?));
+        }
+    }
+
+    fn comparison_expr(&mut self) -> Result<Expr> {
+        let left = self.sum()?;
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.comparison() {
+            Some(comparison) => {
+                let span = self.span(start);
+                let right = self.sum()?;
+                Ok(Expr::Binary(
+                    Box::new(left),
+                    Operator::Compare(comparison),
+                    Box::new(right),
+                    span,
+                ))
+            }
+            None => Ok(left),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expr> {
+        self.binary(&[("+", Operator::Add), ("-", Operator::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> Result<Expr> {
+        self.binary(
+            &[
+                ("*", Operator::Mul),
+                ("/", Operator::Div),
+                ("%", Operator::Rem),
+            ],
+            Self::unary_expr,
+        )
+    }
+
+    /// Parses left-associative operators of the same precedence.
+    fn binary(
+        &mut self,
+        operators: &[(&str, Operator)],
+        operand: fn(&mut Self) -> Result<Expr>,
+    ) -> Result<Expr> {
+        let mut expr = operand(self)?;
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            let operator = match operators.iter().find(|(op, _)| self.eat(op)) {
+                Some((_, operator)) => *operator,
+                None => return Ok(expr),
+            };
+            let span = self.span(start);
+            expr = Expr::Binary(Box::new(expr), operator, Box::new(operand(self)?), span);
+        }
+    }
+
+    fn unary_expr(&mut self) -> Result<Expr> {
+        self.skip_whitespace();
+        let start = self.pos;
+        if self.rest().starts_with('!') && !self.rest().starts_with("!=") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.unary_expr()?)));
+        }
+        if self.eat("-") {
+            let span = self.span(start);
+            return Ok(Expr::Neg(Box::new(self.unary_expr()?), span));
+        }
+
+        let expr = self.primary()?;
+        if self.peek() == Some('[') || self.starts_with_dot_selector() {
+            return Ok(Expr::Suffix(Box::new(expr), self.query()?));
+        }
+        Ok(expr)
+    }
+
+    /// Returns true if the rest of the pipeline starts with a `.` or `..`
+    /// followed by a selector.
+    fn starts_with_dot_selector(&self) -> bool {
+        let rest = self.rest();
+        let rest = rest
+            .strip_prefix("..")
+            .or_else(|| rest.strip_prefix('.'))
+            .unwrap_or("");
+        rest.starts_with(|c: char| is_key_char(c) || c == '*' || c == '"' || c == '[')
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('.') => Ok(Expr::Path(self.query()?)),
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.pipe()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some('[') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                if self.eat("]") {
+                    return Ok(Expr::List(None));
+                }
+                let expr = self.pipe()?;
+                self.expect("]")?;
+                Ok(Expr::List(Some(Box::new(expr))))
+            }
+            Some('{') => self.dict(),
+            Some(c) if c == '"' || c.is_ascii_digit() => Ok(Expr::Literal(self.literal()?)),
+            Some(c) if is_key_char(c) => self.word(),
+            _ => Err(self.error("expected an expression")),
+        }
+    }
+
+    /// Parses a function call, a `true`, `false` or `none` literal, or a path
+    /// starting with a key without a `.`.
+    fn word(&mut self) -> Result<Expr> {
+        let start = self.pos;
+        let word = self.key();
+
+        if matches!(word.as_str(), "true" | "false" | "none") {
+            self.pos = start;
+            return Ok(Expr::Literal(self.literal()?));
+        }
+
+        if let Some(&(_, builtin, arity)) = BUILTINS.iter().find(|(name, _, _)| *name == word) {
+            let span = self.span(start);
+            let mut args = Vec::new();
+            if arity > 0 {
+                self.expect("(")?;
+                args.push(self.pipe()?);
+                while args.len() < arity {
+                    self.expect(";")?;
+                    args.push(self.pipe()?);
+                }
+                self.expect(")")?;
+            }
+            return Ok(Expr::Call(builtin, args, span));
+        }
+
+        if self.peek() == Some('(') {
+            self.pos = start;
+            return Err(self.error(&format!("unknown function `{}`", word)));
+        }
+
+        let mut query = self.query()?;
+        query
+            .segments
+            .insert(0, Segment::Child(Selector::Key(word)));
+        Ok(Expr::Path(query))
+    }
+
+    /// Parses a `{ key: value, ... }` dictionary. Entries can be separated
+    /// by commas or whitespace.
+    fn dict(&mut self) -> Result<Expr> {
+        self.expect("{")?;
+        let mut entries = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat("}") {
+                return Ok(Expr::Dict(entries));
+            }
+
+            let key = match self.peek() {
+                Some('"') => self.string()?,
+                Some(c) if is_key_char(c) => self.key(),
+                _ => return Err(self.error("expected a key or `}`")),
+            };
+
+            self.skip_whitespace();
+            let value = if self.eat(":") {
+                self.disjunction()?
+            } else {
+                Expr::Path(Query {
+                    segments: vec![Segment::Child(Selector::Key(key.clone()))],
+                })
+            };
+            entries.push((key, value));
+
+            self.skip_whitespace();
+            self.eat(",");
+        }
+    }
+}
@@ -1,14 +1,54 @@
-use std::{collections::HashMap, fmt::Display};
+use std::{borrow::Cow, fmt::Display};
+
+use indexmap::IndexMap;
+
+use crate::Emitter;
+
+/// Dictionary type used by `Value::Dict`.
+///
+/// Entries are kept in the order they appear in the source, so iterating over
+/// a dictionary (and printing it) is deterministic, while key lookup stays
+/// O(1). Note that comparing two dictionaries with `==` ignores their order.
+pub type Dict<'s> = IndexMap<Cow<'s, str>, Value<'s>>;
+
+/// A `Value` that owns all of its strings, so it does not borrow from the
+/// source it was parsed from.
+///
+/// Convert a `Value` into one with `Value::into_owned` or
+/// `Value::to_owned_value`, and borrow one back as a `Value` with
+/// `Value::as_borrowed`.
+///
+/// # Examples
+/// ```
+/// use dent_parse::{Dent, OwnedValue, Value};
+///
+/// struct Config {
+///     value: OwnedValue,
+/// }
+///
+/// let source = String::from("{ name: Mario }");
+/// let config = Config {
+///     value: Dent::default().parse(&source).unwrap().into_owned(),
+/// };
+/// drop(source);
+///
+/// assert_eq!(config.value["name"], Value::Str("Mario".into()));
+/// ```
+pub type OwnedValue = Value<'static>;
 
 /// Value type returned by Dent.
 ///
 /// Represents a value in Dent, which can be a string, integer, float, boolean,
 /// list or dictionary.
 ///
-/// Values are stored as references to the original string, so they are only
-/// valid as long as the original string is valid. For values returned by parsing
-/// files, whether by `Dent::parse_file` or the `@import` function, they should
-/// be valid for the lifetime of the parser object.
+/// Strings are stored as references to the original string where possible, so
+/// values are only valid as long as the original string is valid. Strings
+/// containing escape sequences are unescaped into owned strings. Values
+/// returned by parsing files, whether by `Dent::parse_file` or the `@import`
+/// function, own all of their strings.
+///
+/// To keep a value after the source is dropped, convert it into an
+/// `OwnedValue` with `Value::into_owned`.
 ///
 /// # Accessing values
 ///
@@ -40,18 +80,20 @@ use std::{collections::HashMap, fmt::Display};
 /// ```
 #[derive(Debug, PartialEq, Clone)]
 pub enum Value<'s> {
+    /// No value, written as the bare word `none`. Quoted, `"none"` is a
+    /// string, and as a dictionary key `none` is a key like any other.
     None,
-    Str(&'s str),
+    Str(Cow<'s, str>),
     Int(i64),
     Float(f64),
     Bool(bool),
     List(Vec<Value<'s>>),
-    Dict(HashMap<&'s str, Value<'s>>),
+    Dict(Dict<'s>),
 }
 
 impl<'s> Value<'s> {
     /// Returns the underlying string value, if it is one
-    pub fn as_str(&self) -> Option<&'s str> {
+    pub fn as_str(&self) -> Option<&str> {
         match self {
             Value::Str(s) => Some(s),
             _ => None,
@@ -91,7 +133,7 @@ impl<'s> Value<'s> {
     }
 
     /// Returns the underlying dictionary value, if it is one
-    pub fn as_dict(&self) -> Option<&HashMap<&'s str, Value<'s>>> {
+    pub fn as_dict(&self) -> Option<&Dict<'s>> {
         match self {
             Value::Dict(d) => Some(d),
             _ => None,
@@ -133,6 +175,20 @@ impl<'s> Value<'s> {
         matches!(self, Value::Dict(_))
     }
 
+    /// Returns the name of the value's type, as used in messages: `none`,
+    /// `string`, `int`, `float`, `bool`, `list` or `dict`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::None => "none",
+            Value::Str(_) => "string",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::List(_) => "list",
+            Value::Dict(_) => "dict",
+        }
+    }
+
     /// Returns the length of the value, if it is a list or dictionary
     pub fn len(&self) -> Option<usize> {
         match self {
@@ -150,6 +206,49 @@ impl<'s> Value<'s> {
             _ => false,
         }
     }
+
+    /// Converts the value into an `OwnedValue`, copying every string that is
+    /// still borrowed from the source.
+    pub fn into_owned(self) -> OwnedValue {
+        match self {
+            Value::None => Value::None,
+            Value::Str(s) => Value::Str(Cow::Owned(s.into_owned())),
+            Value::Int(i) => Value::Int(i),
+            Value::Float(f) => Value::Float(f),
+            Value::Bool(b) => Value::Bool(b),
+            Value::List(l) => Value::List(l.into_iter().map(Value::into_owned).collect()),
+            Value::Dict(d) => Value::Dict(
+                d.into_iter()
+                    .map(|(k, v)| (Cow::Owned(k.into_owned()), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns a copy of the value as an `OwnedValue`.
+    pub fn to_owned_value(&self) -> OwnedValue {
+        self.as_borrowed().into_owned()
+    }
+
+    /// Returns a copy of the value with every string borrowed from this one.
+    ///
+    /// This is the cheap way back from an `OwnedValue` to a `Value` with a
+    /// shorter lifetime, as no strings are copied.
+    pub fn as_borrowed(&self) -> Value<'_> {
+        match self {
+            Value::None => Value::None,
+            Value::Str(s) => Value::Str(Cow::Borrowed(s.as_ref())),
+            Value::Int(i) => Value::Int(*i),
+            Value::Float(f) => Value::Float(*f),
+            Value::Bool(b) => Value::Bool(*b),
+            Value::List(l) => Value::List(l.iter().map(Value::as_borrowed).collect()),
+            Value::Dict(d) => Value::Dict(
+                d.iter()
+                    .map(|(k, v)| (Cow::Borrowed(k.as_ref()), v.as_borrowed()))
+                    .collect(),
+            ),
+        }
+    }
 }
 
 impl<'i, 's> std::ops::Index<&'i str> for Value<'s> {
@@ -166,7 +265,7 @@ impl<'i, 's> std::ops::Index<&'i str> for Value<'s> {
 impl<'s> std::ops::IndexMut<&'s str> for Value<'s> {
     fn index_mut(&mut self, key: &'s str) -> &mut Self::Output {
         match self {
-            Value::Dict(d) => d.entry(key).or_insert(Value::None),
+            Value::Dict(d) => d.entry(Cow::Borrowed(key)).or_insert(Value::None),
             _ => panic!("Cannot index non-dict value"),
         }
     }
@@ -195,28 +294,22 @@ impl<'s> std::ops::IndexMut<usize> for Value<'s> {
     }
 }
 
+impl<'s> From<&'s str> for Value<'s> {
+    fn from(s: &'s str) -> Self {
+        Value::Str(Cow::Borrowed(s))
+    }
+}
+
+impl<'s> From<String> for Value<'s> {
+    fn from(s: String) -> Self {
+        Value::Str(Cow::Owned(s))
+    }
+}
+
+/// Writes the value as Dent text on a single line, as `Emitter::compact` does,
+/// so the output parses back into the same value.
 impl<'s> Display for Value<'s> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Value::None => write!(f, "none"),
-            Value::Str(s) => write!(f, "{}", s),
-            Value::Int(i) => write!(f, "{}", i),
-            Value::Float(fl) => write!(f, "{}", fl),
-            Value::Bool(b) => write!(f, "{}", b),
-            Value::List(l) => {
-                write!(f, "[")?;
-                for v in l.iter() {
-                    write!(f, " {}", v)?;
-                }
-                write!(f, " ]")
-            }
-            Value::Dict(d) => {
-                write!(f, "{{")?;
-                for (k, v) in d.iter() {
-                    write!(f, " {}: {}", k, v)?;
-                }
-                write!(f, " }}")
-            }
-        }
+        f.write_str(&Emitter::compact().emit(self))
     }
 }
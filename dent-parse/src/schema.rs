@@ -0,0 +1,640 @@
+//! Validating `Value`s against schemas written in Dent.
+//!
+//! A schema is either the name of a type, such as `int`, or a dictionary
+//! with any of these keys:
+//! - `type`: the name of a type, or a list of them, of which the value must
+//!   have one. The types are `none`, `string`, `int`, `float`, `number` (an
+//!   int or a float), `bool`, `list`, `dict` and `any`. As `none` is also a
+//!   value, it can be written with or without quotes.
+//! - `enum`: a list of the values the value must be one of.
+//! - `min` and `max`: the inclusive range a number must be in.
+//! - `min_length` and `max_length`: the inclusive range of the number of
+//!   items in a list, or of characters in a string.
+//! - `pattern`: a regular expression a string must match. It matches
+//!   anywhere in the string unless it is anchored with `^` and `$`.
+//! - `items`: the schema of every item of a list.
+//! - `keys`: a dictionary of the schemas of the entries of a dictionary.
+//!   Entries are optional unless their schema has `required: true`.
+//! - `default`: the value of a dictionary entry that is missing, used when
+//!   normalizing. It must match the rest of the schema, which cannot also
+//!   have `required: true`.
+//! - `additional_keys`: whether a dictionary may have entries that are not in
+//!   `keys`, or the schema of their values. They are allowed by default.
+//! - `description`: a description of the value, which is not checked.
+//!
+//! A schema with `keys` or `additional_keys` and no `type` only accepts
+//! dictionaries, and one with `items` and no `type` only accepts lists.
+//!
+//! Validation reports every violation of the schema, rather than stopping at
+//! the first.
+//!
+//! Normalizing with `Schema::normalize` also validates a value, and returns a
+//! copy of it that can be used without checking it again: the defaults of
+//! missing entries are filled in, ints are converted to floats where only a
+//! float is expected, and single values are wrapped in a list where a list is
+//! expected.
+//!
+//! # Examples
+//! ```
+//! use dent_parse::{schema::Schema, Dent};
+//!
+//! let dent = Dent::default();
+//! let schema = Schema::from_value(&dent.parse(
+//!     "{
+//!         keys: {
+//!             name: { type: string required: true }
+//!             lives: { type: int min: 0 max: 99 }
+//!             skills: { items: { enum: [ jumps grows flies ] } }
+//!         }
+//!         additional_keys: false
+//!     }"
+//! ).unwrap()).unwrap();
+//!
+//! let (value, locations) = dent
+//!     .parse_with_locations("{ lives: 100 skills: [ jumps swims ] age: 35 }")
+//!     .unwrap();
+//!
+//! let messages: Vec<String> = schema
+//!     .validate_located(&value, &locations)
+//!     .iter()
+//!     .map(|v| format!("{}: {}", v.location.as_ref().unwrap(), v))
+//!     .collect();
+//! assert_eq!(messages, vec![
+//!     "1:1: name: the required key is missing",
+//!     "1:10: lives: expected at most 99, found 100",
+//!     "1:30: skills[1]: expected one of jumps, grows or flies, found swims",
+//!     "1:43: age: the key is not allowed by the schema",
+//! ]);
+//! ```
+
+use std::{cmp::Ordering, fmt::Display};
+
+use indexmap::IndexMap;
+use regex::Regex;
+
+use crate::{
+    key_path::KeyPath, Dict, Emitter, Error, Location, Locations, OwnedValue, Result, Value,
+};
+
+/// A description of the values a document may have, read from a `Value`
+/// with `Schema::from_value`.
+#[derive(Clone, Debug, Default)]
+pub struct Schema {
+    /// The types the value may have, any type if empty
+    types: Vec<Type>,
+    required: bool,
+    default: Option<OwnedValue>,
+    values: Option<Vec<OwnedValue>>,
+    min: Option<OwnedValue>,
+    max: Option<OwnedValue>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pattern: Option<Regex>,
+    items: Option<Box<Schema>>,
+    keys: IndexMap<String, Schema>,
+    additional_keys: AdditionalKeys,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Type {
+    None,
+    String,
+    Int,
+    Float,
+    Number,
+    Bool,
+    List,
+    Dict,
+    Any,
+}
+
+#[derive(Clone, Debug, Default)]
+enum AdditionalKeys {
+    #[default]
+    Allowed,
+    Denied,
+    Schema(Box<Schema>),
+}
+
+/// A value that does not match its schema.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Violation {
+    /// The key path of the value, empty for the root
+    pub path: String,
+    pub message: String,
+    /// Where the value is in the source, if locations were given
+    pub location: Option<Location>,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl Type {
+    const NAMES: [(&'static str, Type); 9] = [
+        ("none", Type::None),
+        ("string", Type::String),
+        ("int", Type::Int),
+        ("float", Type::Float),
+        ("number", Type::Number),
+        ("bool", Type::Bool),
+        ("list", Type::List),
+        ("dict", Type::Dict),
+        ("any", Type::Any),
+    ];
+
+    fn from_name(name: &str) -> Option<Type> {
+        Self::NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, t)| *t)
+    }
+
+    fn name(self) -> &'static str {
+        Self::NAMES.iter().find(|(_, t)| *t == self).unwrap().0
+    }
+
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            Type::None => value.is_none(),
+            Type::String => value.is_str(),
+            Type::Int => value.is_int(),
+            Type::Float => value.is_float(),
+            Type::Number => value.is_int() || value.is_float(),
+            Type::Bool => value.is_bool(),
+            Type::List => value.is_list(),
+            Type::Dict => value.is_dict(),
+            Type::Any => true,
+        }
+    }
+}
+
+impl Schema {
+    /// Reads a schema from a value, usually parsed from a Dent file.
+    ///
+    /// Fails with `Error::InvalidSchema` if the value is not a valid schema,
+    /// for example if it has a key or type name that does not exist.
+    pub fn from_value(value: &Value) -> Result<Schema> {
+        Self::from_value_at(value, KeyPath::Root)
+    }
+
+    fn from_value_at(value: &Value, path: KeyPath) -> Result<Schema> {
+        let dict = match value {
+            Value::Str(name) => {
+                return Ok(Schema {
+                    types: vec![parse_type(name, path)?],
+                    ..Schema::default()
+                })
+            }
+            Value::None => {
+                return Ok(Schema {
+                    types: vec![Type::None],
+                    ..Schema::default()
+                })
+            }
+            Value::Dict(dict) => dict,
+            v => {
+                return Err(invalid(
+                    path,
+                    format!(
+                        "expected a type name or a dictionary, found {}",
+                        v.type_name()
+                    ),
+                ))
+            }
+        };
+
+        let mut schema = Schema::default();
+        for (key, value) in dict {
+            let path = KeyPath::Key(&path, key);
+            match key.as_ref() {
+                "type" => {
+                    schema.types = match value {
+                        Value::List(names) => names
+                            .iter()
+                            .enumerate()
+                            .map(|(i, name)| match name {
+                                Value::Str(name) => parse_type(name, KeyPath::Index(&path, i)),
+                                Value::None => Ok(Type::None),
+                                v => Err(expected(KeyPath::Index(&path, i), "a type name", v)),
+                            })
+                            .collect::<Result<_>>()?,
+                        Value::Str(name) => vec![parse_type(name, path)?],
+                        Value::None => vec![Type::None],
+                        v => return Err(expected(path, "a type name or a list of them", v)),
+                    }
+                }
+                "required" => {
+                    schema.required = value
+                        .as_bool()
+                        .ok_or_else(|| expected(path, "a bool", value))?
+                }
+                "default" => schema.default = Some(value.to_owned_value()),
+                "enum" => {
+                    schema.values = Some(
+                        value
+                            .as_list()
+                            .ok_or_else(|| expected(path, "a list of values", value))?
+                            .iter()
+                            .map(Value::to_owned_value)
+                            .collect(),
+                    )
+                }
+                "min" => schema.min = Some(parse_number(value, path)?),
+                "max" => schema.max = Some(parse_number(value, path)?),
+                "min_length" => schema.min_length = Some(parse_length(value, path)?),
+                "max_length" => schema.max_length = Some(parse_length(value, path)?),
+                "pattern" => {
+                    let pattern = value
+                        .as_str()
+                        .ok_or_else(|| expected(path, "a regular expression", value))?;
+                    schema.pattern =
+                        Some(Regex::new(pattern).map_err(|e| invalid(path, e.to_string()))?);
+                }
+                "items" => schema.items = Some(Box::new(Self::from_value_at(value, path)?)),
+                "keys" => {
+                    let keys = value
+                        .as_dict()
+                        .ok_or_else(|| expected(path, "a dictionary of schemas", value))?;
+                    for (key, value) in keys {
+                        let schema_path = KeyPath::Key(&path, key);
+                        schema
+                            .keys
+                            .insert(key.to_string(), Self::from_value_at(value, schema_path)?);
+                    }
+                }
+                "additional_keys" => {
+                    schema.additional_keys = match value {
+                        Value::Bool(true) => AdditionalKeys::Allowed,
+                        Value::Bool(false) => AdditionalKeys::Denied,
+                        v => AdditionalKeys::Schema(Box::new(Self::from_value_at(v, path)?)),
+                    }
+                }
+                "description" => {}
+                _ => return Err(invalid(path, format!("unknown schema key `{}`", key))),
+            }
+        }
+
+        if schema.types.is_empty() {
+            if !schema.keys.is_empty() || dict.contains_key("additional_keys") {
+                schema.types.push(Type::Dict);
+            }
+            if schema.items.is_some() {
+                schema.types.push(Type::List);
+            }
+        }
+
+        if let Some(default) = schema.default.take() {
+            let default_path = KeyPath::Key(&path, "default");
+            if schema.required {
+                let message = "a required key cannot have a default".to_string();
+                return Err(invalid(default_path, message));
+            }
+            // The default is stored normalized, as it is used as it is
+            let default = schema
+                .normalize(&default)
+                .map_err(|violations| invalid(default_path, violations[0].to_string()))?;
+            schema.default = Some(default);
+        }
+
+        Ok(schema)
+    }
+
+    /// Checks a value against the schema, returning every violation, or
+    /// nothing if the value is valid.
+    ///
+    /// The violations have no locations. Use `Schema::validate_located` with
+    /// a value parsed by `Dent::parse_with_locations` to get them.
+    pub fn validate(&self, value: &Value) -> Vec<Violation> {
+        self.check(value, None, false).1
+    }
+
+    /// Checks a value against the schema like `Schema::validate`, locating
+    /// the violations with the locations of the value's source.
+    ///
+    /// A missing key is located at the dictionary it is missing from.
+    pub fn validate_located(&self, value: &Value, locations: &Locations) -> Vec<Violation> {
+        self.check(value, Some(locations), false).1
+    }
+
+    /// Checks a value against the schema like `Schema::validate`, and returns
+    /// a copy of it with its defaults filled in and its values coerced to the
+    /// types of the schema.
+    ///
+    /// Fails with every violation that is left after normalizing.
+    ///
+    /// # Examples
+    /// ```
+    /// use dent_parse::{schema::Schema, Dent, Value};
+    ///
+    /// let dent = Dent::default();
+    /// let schema = Schema::from_value(&dent.parse(
+    ///     "{ keys: { speed: float tags: { type: list default: [] } } }"
+    /// ).unwrap()).unwrap();
+    ///
+    /// let value = schema.normalize(&dent.parse("{ speed: 2 }").unwrap()).unwrap();
+    /// assert_eq!(value["speed"], Value::Float(2.0));
+    /// assert_eq!(value["tags"], Value::List(vec![]));
+    /// ```
+    pub fn normalize(&self, value: &Value) -> std::result::Result<OwnedValue, Vec<Violation>> {
+        Self::normalized(value, self.check(value, None, true))
+    }
+
+    /// Normalizes a value like `Schema::normalize`, locating the violations
+    /// with the locations of the value's source.
+    pub fn normalize_located(
+        &self,
+        value: &Value,
+        locations: &Locations,
+    ) -> std::result::Result<OwnedValue, Vec<Violation>> {
+        Self::normalized(value, self.check(value, Some(locations), true))
+    }
+
+    fn check(
+        &self,
+        value: &Value,
+        locations: Option<&Locations>,
+        normalize: bool,
+    ) -> (Option<OwnedValue>, Vec<Violation>) {
+        let mut validator = Validator {
+            locations,
+            normalize,
+            violations: Vec::new(),
+        };
+        let normalized = validator.check(self, value, KeyPath::Root);
+        (normalized, validator.violations)
+    }
+
+    fn normalized(
+        value: &Value,
+        (normalized, violations): (Option<OwnedValue>, Vec<Violation>),
+    ) -> std::result::Result<OwnedValue, Vec<Violation>> {
+        if violations.is_empty() {
+            Ok(normalized.unwrap_or_else(|| value.to_owned_value()))
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+fn invalid(path: KeyPath, message: String) -> Error {
+    Error::InvalidSchema {
+        path: path.to_string(),
+        message,
+    }
+}
+
+fn expected(path: KeyPath, what: &str, found: &Value) -> Error {
+    invalid(
+        path,
+        format!("expected {}, found {}", what, found.type_name()),
+    )
+}
+
+fn parse_type(name: &str, path: KeyPath) -> Result<Type> {
+    Type::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Type::NAMES.iter().map(|(n, _)| *n).collect();
+        invalid(
+            path,
+            format!(
+                "unknown type `{}`, expected {}",
+                name,
+                one_of(&names.iter().map(|n| n.to_string()).collect::<Vec<_>>())
+            ),
+        )
+    })
+}
+
+fn parse_number(value: &Value, path: KeyPath) -> Result<OwnedValue> {
+    match value {
+        Value::Int(_) | Value::Float(_) => Ok(value.to_owned_value()),
+        v => Err(expected(path, "a number", v)),
+    }
+}
+
+fn parse_length(value: &Value, path: KeyPath) -> Result<usize> {
+    value
+        .as_int()
+        .and_then(|i| usize::try_from(i).ok())
+        .ok_or_else(|| expected(path, "a non-negative int", value))
+}
+
+/// Lists alternatives, as in `a, b or c`.
+fn one_of(items: &[String]) -> String {
+    match items.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+    }
+}
+
+/// Compares two numbers, which may be ints or floats.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Int(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+        (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f64)),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        _ => None,
+    }
+}
+
+fn count(n: usize, what: &str) -> String {
+    if n == 1 {
+        format!("1 {}", what)
+    } else {
+        format!("{} {}s", n, what)
+    }
+}
+
+struct Validator<'l> {
+    locations: Option<&'l Locations>,
+    /// Whether defaults are filled in and values coerced
+    normalize: bool,
+    violations: Vec<Violation>,
+}
+
+impl Validator<'_> {
+    /// Checks a value, returning its normalized copy if normalizing changed
+    /// it.
+    fn check(&mut self, schema: &Schema, value: &Value, path: KeyPath) -> Option<OwnedValue> {
+        if !schema.types.is_empty() && !schema.types.iter().any(|t| t.matches(value)) {
+            if let Some(coerced) = self.coerce(schema, value) {
+                let normalized = self.check(schema, &coerced, path);
+                return Some(normalized.unwrap_or(coerced));
+            }
+            let names: Vec<String> = schema.types.iter().map(|t| t.name().to_string()).collect();
+            let message = format!("expected {}, found {}", one_of(&names), value.type_name());
+            self.violation(path, message);
+            return None;
+        }
+
+        if let Some(values) = &schema.values {
+            if !values.iter().any(|v| v == value) {
+                let emitter = Emitter::compact();
+                let names: Vec<String> = values.iter().map(|v| emitter.emit(v)).collect();
+                let message = format!(
+                    "expected one of {}, found {}",
+                    one_of(&names),
+                    emitter.emit(value)
+                );
+                self.violation(path, message);
+            }
+        }
+
+        if let Some(min) = &schema.min {
+            if compare(value, min) == Some(Ordering::Less) {
+                self.violation(path, format!("expected at least {}, found {}", min, value));
+            }
+        }
+        if let Some(max) = &schema.max {
+            if compare(value, max) == Some(Ordering::Greater) {
+                self.violation(path, format!("expected at most {}, found {}", max, value));
+            }
+        }
+
+        let length = match value {
+            Value::Str(s) => Some((s.chars().count(), "character")),
+            Value::List(l) => Some((l.len(), "item")),
+            _ => None,
+        };
+        if let Some((length, what)) = length {
+            if let Some(min) = schema.min_length.filter(|min| length < *min) {
+                let message = format!("expected at least {}, found {}", count(min, what), length);
+                self.violation(path, message);
+            }
+            if let Some(max) = schema.max_length.filter(|max| length > *max) {
+                let message = format!("expected at most {}, found {}", count(max, what), length);
+                self.violation(path, message);
+            }
+        }
+
+        if let (Some(pattern), Value::Str(s)) = (&schema.pattern, value) {
+            if !pattern.is_match(s) {
+                let message = format!("`{}` does not match the pattern `{}`", s, pattern.as_str());
+                self.violation(path, message);
+            }
+        }
+
+        match value {
+            Value::List(items) => self.check_list(schema, items, path),
+            Value::Dict(dict) => self.check_dict(schema, dict, path),
+            _ => None,
+        }
+    }
+
+    /// Converts a value that has none of the types of its schema into one
+    /// that does, if normalizing: an int into a float, or a single value into
+    /// a list of it.
+    fn coerce(&self, schema: &Schema, value: &Value) -> Option<OwnedValue> {
+        if !self.normalize {
+            return None;
+        }
+        match value {
+            Value::Int(i) if schema.types.contains(&Type::Float) => Some(Value::Float(*i as f64)),
+            Value::None => None,
+            v if schema.types.contains(&Type::List) => Some(Value::List(vec![v.to_owned_value()])),
+            _ => None,
+        }
+    }
+
+    fn check_list(
+        &mut self,
+        schema: &Schema,
+        items: &[Value],
+        path: KeyPath,
+    ) -> Option<OwnedValue> {
+        let item_schema = schema.items.as_ref()?;
+
+        let normalized: Vec<Option<OwnedValue>> = items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| self.check(item_schema, item, KeyPath::Index(&path, i)))
+            .collect();
+
+        if normalized.iter().all(Option::is_none) {
+            return None;
+        }
+        Some(Value::List(
+            items
+                .iter()
+                .zip(normalized)
+                .map(|(item, normalized)| normalized.unwrap_or_else(|| item.to_owned_value()))
+                .collect(),
+        ))
+    }
+
+    fn check_dict(&mut self, schema: &Schema, dict: &Dict, path: KeyPath) -> Option<OwnedValue> {
+        let mut defaults = Vec::new();
+        for (key, key_schema) in &schema.keys {
+            if dict.contains_key(key.as_str()) {
+                continue;
+            }
+            match &key_schema.default {
+                Some(default) if self.normalize => defaults.push((key, default)),
+                Some(_) => {}
+                None if key_schema.required => {
+                    let message = "the required key is missing".to_string();
+                    self.violation(KeyPath::Key(&path, key), message);
+                }
+                None => {}
+            }
+        }
+
+        let mut normalized = Vec::with_capacity(dict.len());
+        for (key, value) in dict {
+            let key_path = KeyPath::Key(&path, key);
+            normalized.push(
+                match (schema.keys.get(key.as_ref()), &schema.additional_keys) {
+                    (Some(key_schema), _) => self.check(key_schema, value, key_path),
+                    (None, AdditionalKeys::Allowed) => None,
+                    (None, AdditionalKeys::Denied) => {
+                        let message = "the key is not allowed by the schema".to_string();
+                        self.violation(key_path, message);
+                        None
+                    }
+                    (None, AdditionalKeys::Schema(value_schema)) => {
+                        self.check(value_schema, value, key_path)
+                    }
+                },
+            );
+        }
+
+        if defaults.is_empty() && normalized.iter().all(Option::is_none) {
+            return None;
+        }
+        // Defaults are added after the keys that are there, in the order of
+        // the schema
+        let mut result: Dict<'static> = dict
+            .iter()
+            .zip(normalized)
+            .map(|((key, value), normalized)| {
+                let value = normalized.unwrap_or_else(|| value.to_owned_value());
+                (key.to_string().into(), value)
+            })
+            .collect();
+        for (key, default) in defaults {
+            result.insert(key.clone().into(), default.clone());
+        }
+        Some(Value::Dict(result))
+    }
+
+    fn violation(&mut self, path: KeyPath, message: String) {
+        let path = path.to_string();
+        let location = self
+            .locations
+            .and_then(|locations| locations.get(&path))
+            .cloned();
+        self.violations.push(Violation {
+            path,
+            message,
+            location,
+        });
+    }
+}
@@ -0,0 +1,414 @@
+//! Serializing Rust types to Dent with `serde`.
+
+use std::borrow::Cow;
+
+use serde::ser::{self, Serialize};
+
+use crate::{Dict, Emitter, Error, OwnedValue, Result, Value};
+
+/// Serializes an instance of `T` into a `Value`.
+///
+/// Enums follow the representation `from_str` reads: unit variants become
+/// strings, and other variants a dictionary with a single entry mapping the
+/// variant name to its content. `Option::None` and `()` become `Value::None`.
+///
+/// Fails if `T` contains an integer that does not fit in an `i64`, or a map
+/// with keys that are not strings, integers or booleans.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<OwnedValue> {
+    value.serialize(ValueSerializer)
+}
+
+/// Serializes an instance of `T` as Dent text on a single line.
+///
+/// Strings are only quoted when they would not be read back as the same
+/// string otherwise.
+///
+/// # Examples
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Character<'a> {
+///     name: &'a str,
+///     title: &'a str,
+///     age: u32,
+/// }
+///
+/// let character = Character { name: "Mario", title: "Super Mario", age: 35 };
+/// assert_eq!(
+///     dent_parse::to_string(&character).unwrap(),
+///     "{ name: Mario title: \"Super Mario\" age: 35 }"
+/// );
+/// ```
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    Ok(Emitter::compact().emit(&to_value(value)?))
+}
+
+/// Serializes an instance of `T` as indented Dent text, laid out by
+/// `Emitter::new`.
+///
+/// To change the layout, serialize into a `Value` with `to_value` and write it
+/// with a configured `Emitter` instead.
+pub fn to_string_pretty<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    Ok(Emitter::new().emit(&to_value(value)?))
+}
+
+impl ser::Error for Error {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        Error::Serde {
+            path: String::new(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+impl<'s> Serialize for Value<'s> {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Value::None => serializer.serialize_none(),
+            Value::Str(s) => serializer.serialize_str(s),
+            Value::Int(i) => serializer.serialize_i64(*i),
+            Value::Float(f) => serializer.serialize_f64(*f),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::List(l) => serializer.collect_seq(l),
+            Value::Dict(d) => serializer.collect_map(d),
+        }
+    }
+}
+
+fn out_of_range(value: impl std::fmt::Display) -> Error {
+    <Error as ser::Error>::custom(format_args!("integer {} is out of range", value))
+}
+
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = OwnedValue;
+    type Error = Error;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeDict;
+    type SerializeStruct = SerializeDict;
+    type SerializeStructVariant = SerializeDict;
+
+    fn serialize_bool(self, v: bool) -> Result<OwnedValue> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<OwnedValue> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<OwnedValue> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<OwnedValue> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<OwnedValue> {
+        Ok(Value::Int(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<OwnedValue> {
+        i64::try_from(v)
+            .map(Value::Int)
+            .map_err(|_| out_of_range(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<OwnedValue> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<OwnedValue> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<OwnedValue> {
+        Ok(Value::Int(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<OwnedValue> {
+        i64::try_from(v)
+            .map(Value::Int)
+            .map_err(|_| out_of_range(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<OwnedValue> {
+        i64::try_from(v)
+            .map(Value::Int)
+            .map_err(|_| out_of_range(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<OwnedValue> {
+        Ok(Value::Float(v.into()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<OwnedValue> {
+        Ok(Value::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<OwnedValue> {
+        Ok(Value::Str(Cow::Owned(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<OwnedValue> {
+        Ok(Value::Str(Cow::Owned(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<OwnedValue> {
+        Ok(Value::List(
+            v.iter().map(|b| Value::Int((*b).into())).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<OwnedValue> {
+        Ok(Value::None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<OwnedValue> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<OwnedValue> {
+        Ok(Value::None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<OwnedValue> {
+        Ok(Value::None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<OwnedValue> {
+        Ok(Value::Str(Cow::Borrowed(variant)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<OwnedValue> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<OwnedValue> {
+        let mut dict = Dict::new();
+        dict.insert(Cow::Borrowed(variant), value.serialize(self)?);
+        Ok(Value::Dict(dict))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList> {
+        Ok(SerializeList {
+            variant: None,
+            values: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeList> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeList> {
+        Ok(SerializeList {
+            variant: Some(variant),
+            values: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeDict> {
+        Ok(SerializeDict {
+            variant: None,
+            values: Dict::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeDict> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeDict> {
+        Ok(SerializeDict {
+            variant: Some(variant),
+            values: Dict::new(),
+            key: None,
+        })
+    }
+}
+
+/// Wraps the content of an enum variant in a dictionary with the variant name
+/// as its only key, or returns it unchanged for other types.
+fn wrap_variant(variant: Option<&'static str>, value: OwnedValue) -> OwnedValue {
+    match variant {
+        Some(variant) => {
+            let mut dict = Dict::new();
+            dict.insert(Cow::Borrowed(variant), value);
+            Value::Dict(dict)
+        }
+        None => value,
+    }
+}
+
+struct SerializeList {
+    variant: Option<&'static str>,
+    values: Vec<OwnedValue>,
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = OwnedValue;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.values.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<OwnedValue> {
+        Ok(wrap_variant(self.variant, Value::List(self.values)))
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = OwnedValue;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<OwnedValue> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = OwnedValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<OwnedValue> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = OwnedValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<OwnedValue> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct SerializeDict {
+    variant: Option<&'static str>,
+    values: Dict<'static>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeDict {
+    type Ok = OwnedValue;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        let key = match key.serialize(ValueSerializer)? {
+            Value::Str(s) => s.into_owned(),
+            Value::Int(i) => i.to_string(),
+            Value::Bool(b) => b.to_string(),
+            _ => {
+                return Err(ser::Error::custom(
+                    "dictionary keys must be strings, integers or booleans",
+                ))
+            }
+        };
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| <Error as ser::Error>::custom("value serialized before key"))?;
+        self.values
+            .insert(Cow::Owned(key), value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<OwnedValue> {
+        Ok(wrap_variant(self.variant, Value::Dict(self.values)))
+    }
+}
+
+impl ser::SerializeStruct for SerializeDict {
+    type Ok = OwnedValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.values
+            .insert(Cow::Borrowed(key), value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<OwnedValue> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeDict {
+    type Ok = OwnedValue;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<OwnedValue> {
+        ser::SerializeMap::end(self)
+    }
+}
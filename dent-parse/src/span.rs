@@ -0,0 +1,186 @@
+use std::{collections::HashMap, fmt::Display, path::Path, sync::Arc};
+
+/// A region of Dent source text.
+///
+/// Spans store both the byte range of the region, for slicing the source, and
+/// the human-readable line and column of its start.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct Span {
+    /// Byte offset of the start of the region
+    pub start: usize,
+    /// Byte offset one past the end of the region
+    pub end: usize,
+    /// Line of the start of the region, starting at 1
+    pub line: usize,
+    /// Column of the start of the region in characters, starting at 1
+    pub column: usize,
+}
+
+impl Span {
+    /// Creates a new span.
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Span {
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+
+    /// Returns the length of the span in bytes.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns true if the span covers no text, as for the end of a file.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the span of the character at a line and byte column of
+    /// `source`, both starting at 1, as reported by parsers of other formats.
+    /// Positions past the end of a line are moved back to its end.
+    #[cfg(feature = "json")]
+    pub(crate) fn at_line_column(source: &str, line: usize, column: usize) -> Span {
+        let line_start: usize = source
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum();
+        let line_len = source[line_start..]
+            .find('\n')
+            .unwrap_or(source.len() - line_start);
+
+        let mut start = line_start + column.saturating_sub(1).min(line_len);
+        while !source.is_char_boundary(start) {
+            start -= 1;
+        }
+        let len = source[start..].chars().next().map_or(0, char::len_utf8);
+        let column = source[line_start..start].chars().count() + 1;
+
+        Span::new(start, start + len, line.max(1), column)
+    }
+
+    /// Returns the span of a byte range of `source`, as reported by parsers
+    /// of other formats.
+    #[cfg(any(feature = "yaml", feature = "toml"))]
+    pub(crate) fn at_range(source: &str, start: usize, end: usize) -> Span {
+        let start = start.min(source.len());
+        let before = &source[..start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Span::new(
+            start,
+            end.clamp(start, source.len()),
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+        )
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A span in a specific source.
+///
+/// The file is `None` for strings parsed with `Dent::parse`, and set to the
+/// canonical path of the file for `Dent::parse_file` and `@import`.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Hash)]
+pub struct Location {
+    pub file: Option<Arc<Path>>,
+    pub span: Span,
+}
+
+impl Location {
+    /// Creates a location in an in-memory string.
+    pub fn new(span: Span) -> Location {
+        Location { file: None, span }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}", file.display(), self.span),
+            None => write!(f, "{}", self.span),
+        }
+    }
+}
+
+/// The locations of the values in a parsed document by key path, as returned
+/// by `Dent::parse_with_locations`.
+///
+/// Key paths are written like `servers[0].name`, and the root has the empty
+/// path. Keys that are empty or have any of `.[]"\` in them are quoted in
+/// brackets, with `"` and `\` escaped, as in `servers[0]["host.name"]`. Values returned by functions, such as the contents of imported files,
+/// have no locations of their own, and are located at the function call.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Locations {
+    map: HashMap<String, Location>,
+}
+
+impl Locations {
+    /// Returns the location of the value at a key path. If the value is not
+    /// in the source itself, the location of the closest value containing it
+    /// is returned instead.
+    pub fn get(&self, path: &str) -> Option<&Location> {
+        let mut path = path;
+        loop {
+            if let Some(location) = self.map.get(path) {
+                return Some(location);
+            }
+            if path.is_empty() {
+                return None;
+            }
+            path = parent(path);
+        }
+    }
+
+    pub(crate) fn insert(&mut self, path: String, location: Location) {
+        self.map.insert(path, location);
+    }
+}
+
+/// Appends a dictionary key to a key path, after a `.` unless it is the
+/// first, or quoted in brackets if it is empty or has characters that would
+/// make the path ambiguous.
+pub(crate) fn write_key<W: std::fmt::Write>(
+    out: &mut W,
+    first: bool,
+    key: &str,
+) -> std::fmt::Result {
+    if key.is_empty() || key.contains(['.', '[', ']', '"', '\\']) {
+        out.write_str("[\"")?;
+        for c in key.chars() {
+            if c == '"' || c == '\\' {
+                out.write_char('\\')?;
+            }
+            out.write_char(c)?;
+        }
+        out.write_str("\"]")
+    } else if first {
+        out.write_str(key)
+    } else {
+        write!(out, ".{}", key)
+    }
+}
+
+/// Returns the key path of the value containing the one at a key path, which
+/// must not be empty.
+fn parent(path: &str) -> &str {
+    let mut last = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in path.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '.' | '[' if !quoted => last = i,
+            _ => {}
+        }
+    }
+    &path[..last]
+}
@@ -0,0 +1,116 @@
+use super::*;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct Character<'a> {
+    name: &'a str,
+    age: u32,
+    skills: Vec<String>,
+    nickname: Option<String>,
+}
+
+#[test]
+fn structs() {
+    let characters: Vec<Character> = from_str(
+        r#"[
+            { name: Mario age: 35 skills: [ jumping ] nickname: "Jumpman" }
+            { name: Luigi age: 33 skills: [] }
+        ]"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        characters,
+        vec![
+            Character {
+                name: "Mario",
+                age: 35,
+                skills: vec!["jumping".into()],
+                nickname: Some("Jumpman".into()),
+            },
+            Character {
+                name: "Luigi",
+                age: 33,
+                skills: vec![],
+                nickname: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn borrowed_strings() {
+    let input = "{ name: Mario }";
+    let name: HashMap<&str, &str> = from_str(input).unwrap();
+
+    assert!(std::ptr::eq(name["name"], &input[8..13]));
+}
+
+#[test]
+fn enums() {
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Power {
+        Mushroom,
+        Star(u32),
+        Flower { color: String },
+    }
+
+    let powers: Vec<Power> =
+        from_str("[ Mushroom { Star: 10 } { Flower: { color: fire } } ]").unwrap();
+
+    assert_eq!(
+        powers,
+        vec![
+            Power::Mushroom,
+            Power::Star(10),
+            Power::Flower {
+                color: "fire".into()
+            },
+        ]
+    );
+}
+
+#[test]
+fn from_parsed_value() {
+    let value = Dent::default().parse("[ 1 2.5 true ]").unwrap();
+    let tuple: (i32, f64, bool) = from_value(&value).unwrap();
+
+    assert_eq!(tuple, (1, 2.5, true));
+}
+
+#[test]
+fn error_path() {
+    #[derive(Deserialize, Debug)]
+    #[allow(dead_code)]
+    struct Game<'a> {
+        #[serde(borrow)]
+        characters: Vec<Character<'a>>,
+    }
+
+    let error = from_str::<Game>(
+        "{ characters: [ { name: Mario age: 35 skills: [] } { name: Luigi age: old skills: [] } ] }",
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        error,
+        Error::Serde {
+            path: "characters[1].age".to_string(),
+            message: "invalid type: string \"old\", expected u32".to_string(),
+        }
+    );
+}
+
+#[test]
+fn missing_field() {
+    let error = from_str::<Character>("{ name: Mario }").unwrap_err();
+
+    assert_eq!(error.to_string(), "missing field `age`");
+}
+
+#[test]
+fn parse_error() {
+    let error = from_str::<Character>("{ name Mario }").unwrap_err();
+
+    assert!(matches!(error, Error::MissingColon { .. }));
+}
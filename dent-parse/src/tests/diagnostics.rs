@@ -0,0 +1,81 @@
+use super::*;
+use crate::diagnostics::{Diagnostic, Severity};
+
+#[test]
+fn missing_colon() {
+    let source = "{\n    name Mario\n}";
+    let error = Dent::default().parse(source).unwrap_err();
+
+    assert_eq!(
+        Diagnostic::from(&error).render(source),
+        "error: expected `:` after dictionary key `name`\n \
+         --> 2:10\n  \
+         |\n\
+         2 |     name Mario\n  \
+         |          ^^^^^ expected `:`, found a string\n  \
+         |\n  \
+         = help: dictionary entries are written as `key: value`\n"
+    );
+}
+
+#[test]
+fn unexpected_eof() {
+    let source = "[ 1 2";
+    let error = Dent::default().parse(source).unwrap_err();
+
+    assert_eq!(
+        Diagnostic::from(&error).render(source),
+        "error: unexpected end of file\n \
+         --> 1:6\n  \
+         |\n\
+         1 | [ 1 2\n  \
+         |      ^ expected more input\n"
+    );
+}
+
+#[test]
+fn secondary_labels() {
+    let source = "a\n\tb\nc";
+    let diagnostic = Diagnostic::new(Severity::Warning, "something")
+        .with_primary(Location::new(Span::new(5, 6, 3, 1)), "here")
+        .with_secondary(Location::new(Span::new(3, 4, 2, 2)), "and here")
+        .with_note("a note");
+
+    assert_eq!(
+        diagnostic.render(source),
+        "warning: something\n \
+         --> 3:1\n  \
+         |\n\
+         2 | \tb\n  \
+         | \t- and here\n\
+         3 | c\n  \
+         | ^ here\n  \
+         |\n  \
+         = note: a note\n"
+    );
+}
+
+#[test]
+fn stale_source() {
+    let diagnostic = Diagnostic::new(Severity::Error, "something")
+        .with_primary(Location::new(Span::new(1, 3, 1, 2)), "here")
+        .with_secondary(Location::new(Span::new(4, 9, 1, 5)), "and here");
+
+    // The spans fall inside a character, or past the end of the source
+    assert_eq!(diagnostic.render("été"), "error: something\n--> 1:2\n");
+    assert_eq!(
+        diagnostic.render("a\r\nb"),
+        "error: something\n \
+         --> 1:2\n  \
+         |\n\
+         1 | a\n  \
+         |  ^ here\n"
+    );
+}
+
+#[test]
+fn no_location() {
+    let diagnostic = Diagnostic::from(Error::Io(std::io::ErrorKind::NotFound));
+
+    assert_eq!(diagnostic.render(""), "error: IO error: entity not found\n");
+}
@@ -0,0 +1,262 @@
+use super::*;
+use crate::document::Document;
+
+const SOURCE: &str = r#"# Characters
+{
+    # The hero
+    name: "Mario"   # quoted on purpose
+    age: 0x23
+    height: 1.55e2
+
+    skills: [
+        jumps # first
+        grows
+    ]
+    friends: @import "examples/dent/dict.dent"
+    empty: {}
+}
+# end
+"#;
+
+#[test]
+fn lossless() {
+    let sources = [
+        SOURCE,
+        "",
+        "# only a comment\n",
+        "  42  ",
+        "[1 2\t3]",
+        "{a:1 b : [ ] c:{x:\"\"\"raw \"\" text\"\"\"}}",
+        "{ key: value } trailing tokens",
+        "{ none: none }",
+        "{ inf: inf -nan: 1 }",
+    ];
+
+    for source in sources {
+        let document = Document::parse(source).unwrap();
+        assert_eq!(document.to_string(), source);
+    }
+}
+
+#[test]
+fn value() {
+    let document = Document::parse(SOURCE).unwrap();
+    let value = document.to_value();
+
+    assert_eq!(value["name"], Value::Str("Mario".into()));
+    assert_eq!(value["age"], Value::Int(35));
+    assert_eq!(value["height"], Value::Float(155.0));
+    assert_eq!(value["skills"][1], Value::Str("grows".into()));
+    assert_eq!(value["friends"], Value::None);
+    assert!(document.root().get("friends").unwrap().is_call());
+    assert_eq!(
+        document.root().keys().collect::<Vec<_>>(),
+        vec!["name", "age", "height", "skills", "friends", "empty"]
+    );
+
+    let document = Document::parse("{ none: none }").unwrap();
+    assert_eq!(document.to_value()["none"], Value::None);
+    assert_eq!(document.root().keys().collect::<Vec<_>>(), vec!["none"]);
+}
+
+#[test]
+fn set() {
+    let mut document = Document::parse(SOURCE).unwrap();
+    let root = document.root_mut();
+
+    root.set("name", &Value::Str("Luigi Mario".into()));
+    root.get_mut("empty").unwrap().set("lives", &Value::Int(3));
+    root.set(
+        "powers",
+        &Dent::default()
+            .parse("[ mushroom star flower feather cape frog tanooki hammer penguin bell ]")
+            .unwrap(),
+    );
+
+    assert_eq!(
+        document.to_string(),
+        r#"# Characters
+{
+    # The hero
+    name: "Luigi Mario"   # quoted on purpose
+    age: 0x23
+    height: 1.55e2
+
+    skills: [
+        jumps # first
+        grows
+    ]
+    friends: @import "examples/dent/dict.dent"
+    empty: { lives: 3 }
+    powers: [
+        mushroom
+        star
+        flower
+        feather
+        cape
+        frog
+        tanooki
+        hammer
+        penguin
+        bell
+    ]
+}
+# end
+"#
+    );
+}
+
+#[test]
+fn list_edits() {
+    let mut document = Document::parse(SOURCE).unwrap();
+    let skills = document.root_mut().get_mut("skills").unwrap();
+
+    skills.insert(1, &Value::Str("runs fast".into()));
+    skills.push(&Value::Bool(true));
+    assert_eq!(
+        skills.remove_index(0).map(|n| n.to_value()),
+        Some(Value::Str("jumps".into()))
+    );
+    assert!(skills.remove_index(10).is_none());
+
+    let mut empty = Document::parse("[]").unwrap();
+    empty.root_mut().push(&Value::Int(1));
+
+    assert_eq!(empty.to_string(), "[ 1 ]");
+    assert!(document
+        .to_string()
+        .contains("    skills: [\n        \"runs fast\"\n        grows\n        true\n    ]\n"));
+}
+
+#[test]
+fn remove() {
+    let mut document = Document::parse(SOURCE).unwrap();
+    let root = document.root_mut();
+
+    assert!(root.remove("name"));
+    assert!(root.remove("height"));
+    assert!(!root.remove("missing"));
+
+    assert_eq!(
+        document.to_string(),
+        r#"# Characters
+{
+    age: 0x23
+
+    skills: [
+        jumps # first
+        grows
+    ]
+    friends: @import "examples/dent/dict.dent"
+    empty: {}
+}
+# end
+"#
+    );
+}
+
+#[test]
+fn replace() {
+    let mut document = Document::parse("{\n  a: { b: 1 } # keep\n}").unwrap();
+    document
+        .root_mut()
+        .get_mut("a")
+        .unwrap()
+        .replace(&Value::Str("two words".into()));
+
+    assert_eq!(document.to_string(), "{\n  a: \"two words\" # keep\n}");
+}
+
+#[test]
+fn errors() {
+    assert!(matches!(
+        Document::parse("{ a 1 }"),
+        Err(Error::MissingColon { .. })
+    ));
+    assert!(matches!(
+        Document::parse("[ 1 "),
+        Err(Error::UnexpectedEof { .. })
+    ));
+    assert!(matches!(
+        Document::parse("0x"),
+        Err(Error::InvalidNumber { .. })
+    ));
+}
+
+#[test]
+fn format() {
+    let document = Document::parse(
+        "  # Characters\n\n\n{\n  # The hero\n        name:   \"Mario\"   # quoted\n  age :0x23\n\n\n\n  skills: [ jumps\n  grows ]\n  friends: @import   \"examples/dent/dict.dent\"\n  notes: [\n  # nothing yet\n  ]\n  long: [ aaaaaaaaaaaaaaaa bbbbbbbbbbbbbbbbbbbbbb cccccccccccccccccccccc dddddddddddddddddd ]\n}\n\n# end",
+    )
+    .unwrap();
+
+    let formatted = document.format(&Emitter::new());
+    assert_eq!(
+        formatted,
+        r#"# Characters
+
+{
+    # The hero
+    name: "Mario" # quoted
+    age: 0x23
+
+    skills: [ jumps grows ]
+    friends: @import "examples/dent/dict.dent"
+    notes: [
+        # nothing yet
+    ]
+    long: [
+        aaaaaaaaaaaaaaaa
+        bbbbbbbbbbbbbbbbbbbbbb
+        cccccccccccccccccccccc
+        dddddddddddddddddd
+    ]
+}
+
+# end
+"#
+    );
+
+    let formatted_again = Document::parse(&formatted).unwrap().format(&Emitter::new());
+    assert_eq!(formatted_again, formatted);
+    assert_eq!(
+        Document::parse(&formatted).unwrap().to_value(),
+        document.to_value()
+    );
+}
+
+#[test]
+fn format_sort_keys() {
+    let document = Document::parse("{\n  # about b\n  b: 1\n  a: { d: 2 c: 3 }\n}").unwrap();
+
+    assert_eq!(
+        document.format(&Emitter::new().with_sort_keys(true).with_indent("  ")),
+        "{\n  a: { c: 3 d: 2 }\n  # about b\n  b: 1\n}\n"
+    );
+}
+
+#[test]
+fn format_empty() {
+    assert_eq!(Document::parse("").unwrap().format(&Emitter::new()), "");
+    assert_eq!(
+        Document::parse("\n# just a comment\n\n")
+            .unwrap()
+            .format(&Emitter::new()),
+        "# just a comment\n"
+    );
+}
+
+#[test]
+fn parse_file() {
+    let document = Document::parse_file("examples/dent/dict.dent").unwrap();
+    assert_eq!(
+        document.to_string(),
+        std::fs::read_to_string("examples/dent/dict.dent").unwrap()
+    );
+
+    let error = Document::parse_file("examples/dent/broken.dent").unwrap_err();
+    assert_eq!(
+        error.location().unwrap().file.as_deref(),
+        Some(Path::new("examples/dent/broken.dent"))
+    );
+}
@@ -0,0 +1,114 @@
+use super::*;
+use std::borrow::Cow;
+
+fn sample() -> OwnedValue {
+    let mut dict = Dict::new();
+    dict.insert("name".into(), "Super Mario".into());
+    dict.insert("true".into(), Value::Bool(true));
+    dict.insert("".into(), Value::None);
+    dict.insert(
+        "numbers".into(),
+        Value::List(vec![
+            Value::Int(i64::MIN),
+            Value::Int(i64::MAX),
+            Value::Float(1.0),
+            Value::Float(-0.5),
+            Value::Float(1e-300),
+            Value::Float(f64::INFINITY),
+        ]),
+    );
+    dict.insert(
+        "strings".into(),
+        Value::List(vec![
+            "".into(),
+            "none".into(),
+            "-inf".into(),
+            "12".into(),
+            "+1x".into(),
+            "a b".into(),
+            "#".into(),
+            "@import".into(),
+            "{".into(),
+            "\"\"\"".into(),
+            "tab\tline\nnul\0bell\u{7}".into(),
+            "ünïcödé".into(),
+            "-".into(),
+            "./".into(),
+        ]),
+    );
+    dict.insert("empty".into(), Value::List(vec![]));
+    dict.insert("nested".into(), Value::Dict(Dict::new()));
+    Value::Dict(dict)
+}
+
+#[test]
+fn round_trip() {
+    let parser = Dent::default();
+    let value = sample();
+
+    for emitter in [
+        Emitter::new(),
+        Emitter::compact(),
+        Emitter::new().with_width(0),
+        Emitter::new().with_indent("\t"),
+        Emitter::new().with_quoting(Quoting::Always),
+    ] {
+        let text = emitter.emit(&value);
+        assert_eq!(parser.parse(&text), Ok(value.clone()), "{}", text);
+    }
+
+    let text = value.to_string();
+    assert_eq!(parser.parse(&text), Ok(value.clone()));
+}
+
+#[test]
+fn layout() {
+    let value = Dent::default()
+        .parse("{ name: Mario skills: [ jumps grows ] friends: [ { name: Luigi } ] }")
+        .unwrap();
+
+    assert_eq!(
+        Emitter::new().emit(&value),
+        "{ name: Mario skills: [ jumps grows ] friends: [ { name: Luigi } ] }\n"
+    );
+    assert_eq!(
+        Emitter::new().with_width(40).emit(&value),
+        "{\n    name: Mario\n    skills: [ jumps grows ]\n    friends: [ { name: Luigi } ]\n}\n"
+    );
+    assert_eq!(
+        Emitter::new()
+            .with_width(0)
+            .with_indent("  ")
+            .with_trailing_newline(false)
+            .emit(&value),
+        "{\n  name: Mario\n  skills: [\n    jumps\n    grows\n  ]\n  friends: [\n    {\n      name: Luigi\n    }\n  ]\n}"
+    );
+}
+
+#[test]
+fn quoting() {
+    let value = Value::List(vec!["plain".into(), "two words".into()]);
+
+    assert_eq!(Emitter::compact().emit(&value), "[ plain \"two words\" ]");
+    assert_eq!(
+        Emitter::compact()
+            .with_quoting(Quoting::Always)
+            .emit(&value),
+        "[ \"plain\" \"two words\" ]"
+    );
+}
+
+#[test]
+fn display() {
+    let value = Value::Dict(
+        vec![
+            (Cow::Borrowed("a key"), Value::Str("true".into())),
+            (Cow::Borrowed("b"), Value::Float(2.0)),
+            (Cow::Borrowed("c"), Value::None),
+        ]
+        .into_iter()
+        .collect(),
+    );
+
+    assert_eq!(value.to_string(), "{ \"a key\": \"true\" b: 2.0 c: none }");
+}
@@ -0,0 +1,106 @@
+use super::*;
+use crate::json;
+
+#[test]
+fn from_json() {
+    let value = json::from_str(
+        r#"{
+            "name": "Mario",
+            "age": 35,
+            "height": 155.0,
+            "rival": null,
+            "skills": ["jumps", "grows"],
+            "flags": { "alive": true, "title": "true", "code": "42" }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        value,
+        Dent::default()
+            .parse(
+                "{ name: Mario age: 35 height: 155.0 rival: none skills: [ jumps grows ] \
+                 flags: { alive: true title: \"true\" code: \"42\" } }"
+            )
+            .unwrap()
+    );
+    assert_eq!(value["height"], Value::Float(155.0));
+    assert_eq!(
+        Emitter::compact().emit(&value["flags"]),
+        "{ alive: true title: \"true\" code: \"42\" }"
+    );
+}
+
+#[test]
+fn to_json() {
+    let value = Dent::default()
+        .parse("{ zeta: none alpha: [ 1 2.5 \"3\" ] mu: { \"key with spaces\": false } }")
+        .unwrap();
+
+    assert_eq!(
+        json::to_string(&value).unwrap(),
+        r#"{"zeta":null,"alpha":[1,2.5,"3"],"mu":{"key with spaces":false}}"#
+    );
+    assert_eq!(
+        json::to_string_pretty(&Value::List(vec![Value::Int(1)])).unwrap(),
+        "[\n  1\n]"
+    );
+}
+
+#[test]
+fn round_trip() {
+    let source = r#"{"a":[1,1.0,-0.5,"none",null],"b":{"":"","inf":"inf"}}"#;
+    let value = json::from_str(source).unwrap();
+
+    assert_eq!(
+        json::to_string(&value).unwrap(),
+        r#"{"a":[1,1.0,-0.5,"none",null],"b":{"":"","inf":"inf"}}"#
+    );
+    assert_eq!(
+        Dent::default()
+            .parse(&Emitter::compact().emit(&value))
+            .unwrap(),
+        value
+    );
+}
+
+#[test]
+fn non_finite_floats() {
+    let value = Dent::default().parse("{ speeds: [ 1.0 inf ] }").unwrap();
+
+    assert_eq!(
+        json::to_string(&value),
+        Err(Error::Conversion {
+            path: "speeds[1]".to_string(),
+            message: "inf cannot be represented in JSON".to_string(),
+        })
+    );
+}
+
+#[test]
+fn out_of_range() {
+    assert_eq!(
+        json::from_str(r#"{ "big": [18446744073709551615] }"#),
+        Err(Error::Conversion {
+            path: "big[0]".to_string(),
+            message: "integer 18446744073709551615 is out of range".to_string(),
+        })
+    );
+}
+
+#[test]
+fn invalid_json() {
+    match json::from_str("{\n  \"a\": 1,\n  \"b\" 2\n}") {
+        Err(Error::InvalidSource {
+            format,
+            message,
+            location,
+        }) => {
+            assert_eq!(format, "JSON");
+            assert_eq!(message, "expected `:`");
+            assert_eq!((location.span.line, location.span.column), (3, 7));
+            assert_eq!(location.span.start, 18);
+        }
+        other => panic!("{:?}", other),
+    }
+}
@@ -1,7 +1,24 @@
+#[cfg(feature = "serde")]
+mod de;
+mod diagnostics;
+mod document;
+mod emit;
+#[cfg(feature = "json")]
+mod json;
 mod parser;
+mod query;
+#[cfg(feature = "schema")]
+mod schema;
+#[cfg(feature = "serde")]
+mod ser;
 mod tokenizer;
+#[cfg(feature = "toml")]
+mod toml;
+#[cfg(feature = "yaml")]
+mod yaml;
 
 use super::*;
+use std::borrow::Cow;
 
 #[test]
 fn access() {
@@ -52,13 +69,13 @@ fn import() {
         parser.parse("@import \"examples/dent/dict.dent\""),
         Ok(Value::Dict(
             vec![
-                ("name", Value::Str("Mario")),
+                ("name".into(), Value::Str("Mario".into())),
                 (
-                    "skills",
-                    Value::List(vec![Value::Str("jumps"), Value::Str("grows")])
+                    "skills".into(),
+                    Value::List(vec![Value::Str("jumps".into()), Value::Str("grows".into())])
                 ),
-                ("age", Value::Int(35)),
-                ("alive", Value::Bool(true)),
+                ("age".into(), Value::Int(35)),
+                ("alive".into(), Value::Bool(true)),
             ]
             .into_iter()
             .collect()
@@ -76,32 +93,32 @@ fn import_mutability() {
         v,
         Value::Dict(
             vec![
-                ("name", Value::Str("Mario")),
+                ("name".into(), Value::Str("Mario".into())),
                 (
-                    "skills",
-                    Value::List(vec![Value::Str("jumps"), Value::Str("grows")])
+                    "skills".into(),
+                    Value::List(vec![Value::Str("jumps".into()), Value::Str("grows".into())])
                 ),
-                ("age", Value::Int(35)),
-                ("alive", Value::Bool(true)),
+                ("age".into(), Value::Int(35)),
+                ("alive".into(), Value::Bool(true)),
             ]
             .into_iter()
             .collect()
         )
     );
 
-    v["name"] = Value::Str("Luigi");
+    v["name"] = Value::Str("Luigi".into());
 
     assert_eq!(
         parser.parse("@import \"examples/dent/dict.dent\""),
         Ok(Value::Dict(
             vec![
-                ("name", Value::Str("Mario")),
+                ("name".into(), Value::Str("Mario".into())),
                 (
-                    "skills",
-                    Value::List(vec![Value::Str("jumps"), Value::Str("grows")])
+                    "skills".into(),
+                    Value::List(vec![Value::Str("jumps".into()), Value::Str("grows".into())])
                 ),
-                ("age", Value::Int(35)),
-                ("alive", Value::Bool(true)),
+                ("age".into(), Value::Int(35)),
+                ("alive".into(), Value::Bool(true)),
             ]
             .into_iter()
             .collect()
@@ -117,16 +134,19 @@ fn import_nested() {
         parser.parse("{ characters: [ @import \"examples/dent/dict.dent\" ] }"),
         Ok(Value::Dict(
             vec![(
-                "characters",
+                "characters".into(),
                 Value::List(vec![Value::Dict(
                     vec![
-                        ("name", Value::Str("Mario")),
+                        ("name".into(), Value::Str("Mario".into())),
                         (
-                            "skills",
-                            Value::List(vec![Value::Str("jumps"), Value::Str("grows")])
+                            "skills".into(),
+                            Value::List(vec![
+                                Value::Str("jumps".into()),
+                                Value::Str("grows".into())
+                            ])
                         ),
-                        ("age", Value::Int(35)),
-                        ("alive", Value::Bool(true)),
+                        ("age".into(), Value::Int(35)),
+                        ("alive".into(), Value::Bool(true)),
                     ]
                     .into_iter()
                     .collect()
@@ -138,6 +158,85 @@ fn import_nested() {
     );
 }
 
+#[test]
+fn import_relative() {
+    let parser = Dent::default();
+
+    let value = parser.parse_file("examples/dent/characters.dent").unwrap();
+    assert_eq!(value["hero"]["name"], Value::Str("Mario".into()));
+
+    // Strings parsed in memory have no file, so they import relative to the
+    // current directory
+    assert_eq!(
+        parser
+            .parse("@import \"examples/dent/characters.dent\"")
+            .unwrap()["hero"]["age"],
+        Value::Int(35)
+    );
+    assert!(matches!(
+        parser.parse("@import \"dict.dent\""),
+        Err(Error::ImportNotFound { .. })
+    ));
+}
+
+#[test]
+fn include_dirs() {
+    let mut parser = Dent::default();
+    parser.add_include_dir("examples/missing");
+    parser.add_include_dir("examples/dent");
+
+    assert_eq!(
+        parser.parse("@import \"dict.dent\"").unwrap()["name"],
+        Value::Str("Mario".into())
+    );
+    // The importing file's directory comes first
+    assert_eq!(
+        parser.parse("@import \"examples/dent/dict.dent\"").unwrap()["age"],
+        Value::Int(35)
+    );
+
+    assert_eq!(
+        parser.parse("[\n  @import \"missing.dent\"\n]"),
+        Err(Error::ImportNotFound {
+            path: "missing.dent".to_string(),
+            tried: vec![
+                PathBuf::from("missing.dent"),
+                PathBuf::from("examples/missing/missing.dent"),
+                PathBuf::from("examples/dent/missing.dent"),
+            ],
+            location: Location::new(Span::new(5, 11, 2, 4)),
+        })
+    );
+    assert_eq!(
+        parser
+            .parse("@import \"missing.dent\"")
+            .unwrap_err()
+            .to_string(),
+        "1:2: Imported file not found: missing.dent (tried missing.dent, \
+         examples/missing/missing.dent, examples/dent/missing.dent)"
+    );
+
+    let paths = std::env::join_paths(["examples/missing", "", "examples/dent"]).unwrap();
+    let mut parser = Dent::default();
+    parser.add_include_dirs_from_paths(paths);
+    assert_eq!(
+        parser.parse("@import \"missing.dent\"").unwrap_err(),
+        Error::ImportNotFound {
+            path: "missing.dent".to_string(),
+            tried: vec![
+                PathBuf::from("missing.dent"),
+                PathBuf::from("examples/missing/missing.dent"),
+                PathBuf::from("examples/dent/missing.dent"),
+            ],
+            location: Location::new(Span::new(1, 7, 1, 2)),
+        }
+    );
+    assert_eq!(
+        parser.parse("@import \"dict.dent\"").unwrap()["name"],
+        Value::Str("Mario".into())
+    );
+}
+
 #[test]
 fn merge_dicts() {
     let parser = Dent::default();
@@ -146,9 +245,9 @@ fn merge_dicts() {
         parser.parse("@merge [ { a: 1 b: 2 } { b: 3 c: 4 } ]"),
         Ok(Value::Dict(
             vec![
-                ("a", Value::Int(1)),
-                ("b", Value::Int(3)),
-                ("c", Value::Int(4)),
+                ("a".into(), Value::Int(1)),
+                ("b".into(), Value::Int(3)),
+                ("c".into(), Value::Int(4)),
             ]
             .into_iter()
             .collect()
@@ -180,7 +279,98 @@ fn recursive() {
     assert_eq!(
         parser.parse_file("examples/dent/recursive.dent"),
         Ok(Value::Dict(
-            vec![("self", Value::None)].into_iter().collect()
+            vec![("self".into(), Value::None)].into_iter().collect()
         ))
     );
 }
+
+#[test]
+fn dict_order() {
+    let parser = Dent::new(HashMap::new());
+
+    let value = parser.parse("{ zeta: 1 alpha: 2 mu: 3 }").unwrap();
+
+    assert_eq!(
+        value.as_dict().unwrap().keys().collect::<Vec<_>>(),
+        vec!["zeta", "alpha", "mu"]
+    );
+    assert_eq!(value.to_string(), "{ zeta: 1 alpha: 2 mu: 3 }");
+}
+
+#[test]
+fn merge_dicts_order() {
+    let parser = Dent::default();
+
+    let value = parser
+        .parse("@merge [ { c: 1 a: 2 } { b: 3 a: 4 } ]")
+        .unwrap();
+
+    assert_eq!(value.to_string(), "{ c: 1 a: 4 b: 3 }");
+}
+
+#[test]
+fn owned() {
+    let parser = Dent::new(HashMap::new());
+
+    let source = String::from("{ name: \"Mario\" skills: [ jumps grows ] }");
+    let value = parser.parse(&source).unwrap();
+    let owned = value.to_owned_value();
+    let into_owned = value.into_owned();
+    drop(source);
+
+    assert_eq!(owned, into_owned);
+    assert_eq!(owned["name"], Value::Str("Mario".into()));
+    assert!(matches!(owned["name"], Value::Str(Cow::Owned(_))));
+    assert!(owned
+        .as_dict()
+        .unwrap()
+        .keys()
+        .all(|k| matches!(k, Cow::Owned(_))));
+
+    let borrowed = owned.as_borrowed();
+    assert_eq!(borrowed, owned);
+    assert!(matches!(
+        borrowed["skills"][1],
+        Value::Str(Cow::Borrowed("grows"))
+    ));
+}
+
+#[test]
+fn parser_dropped() {
+    let parser = Dent::default();
+    let internal = Arc::downgrade(&parser.internal);
+
+    let value = parser.parse_file("examples/dent/dict.dent").unwrap();
+    drop(parser);
+
+    assert!(internal.upgrade().is_none());
+    assert_eq!(value["name"], Value::Str("Mario".into()));
+}
+
+#[test]
+fn clear_imports() {
+    let path = std::env::temp_dir().join(format!("dent-clear-imports-{}.dent", std::process::id()));
+    let parser = Dent::default();
+
+    std::fs::write(&path, "{ version: 1 }").unwrap();
+    assert_eq!(parser.parse_file(&path).unwrap()["version"], Value::Int(1));
+
+    std::fs::write(&path, "{ version: 2 }").unwrap();
+    assert_eq!(parser.parse_file(&path).unwrap()["version"], Value::Int(1));
+
+    parser.clear_imports();
+    assert_eq!(parser.parse_file(&path).unwrap()["version"], Value::Int(2));
+
+    // Imports of the file share the cache, and are invalidated with it
+    let import = format!("@import \"\"\"{}\"\"\"", path.display());
+    assert_eq!(parser.parse(&import).unwrap()["version"], Value::Int(2));
+
+    std::fs::write(&path, "{ version: 3 }").unwrap();
+    assert_eq!(parser.parse(&import).unwrap()["version"], Value::Int(2));
+
+    parser.clear_imports();
+    assert_eq!(parser.parse(&import).unwrap()["version"], Value::Int(3));
+    assert_eq!(parser.parse_file(&path).unwrap()["version"], Value::Int(3));
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -35,12 +35,28 @@ fn empty() {
     assert_eq!(parser.parse(""), Ok(Value::None));
 }
 
+#[test]
+fn none() {
+    let parser = Dent::new(HashMap::new());
+
+    assert_eq!(parser.parse("none"), Ok(Value::None));
+    assert_eq!(
+        parser.parse("[ none \"none\" ]"),
+        Ok(Value::List(vec![Value::None, Value::Str("none".into())]))
+    );
+
+    // As a key, `none` is a word like any other
+    let value = parser.parse("{ none: 1 status: none }").unwrap();
+    assert_eq!(value["none"], Value::Int(1));
+    assert_eq!(value["status"], Value::None);
+}
+
 #[test]
 fn string() {
     let parser = Dent::new(HashMap::new());
 
-    assert_eq!(parser.parse("foo"), Ok(Value::Str("foo")));
-    assert_eq!(parser.parse("\"foo\""), Ok(Value::Str("foo")));
+    assert_eq!(parser.parse("foo"), Ok(Value::Str("foo".into())));
+    assert_eq!(parser.parse("\"foo\""), Ok(Value::Str("foo".into())));
 }
 
 #[test]
@@ -64,7 +80,7 @@ fn dict() {
     assert_eq!(
         parser.parse("{ foo: 1 bar: 2 }"),
         Ok(Value::Dict(
-            vec![("foo", Value::Int(1)), ("bar", Value::Int(2))]
+            vec![("foo".into(), Value::Int(1)), ("bar".into(), Value::Int(2))]
                 .into_iter()
                 .collect()
         ))
@@ -89,7 +105,7 @@ fn multithreaded() {
     for _ in 0..100 {
         let parser = parser.clone();
         threads.push(std::thread::spawn(move || {
-            assert_eq!(parser.parse("foo"), Ok(Value::Str("foo")));
+            assert_eq!(parser.parse("foo"), Ok(Value::Str("foo".into())));
         }));
     }
 
@@ -97,7 +113,7 @@ fn multithreaded() {
         thread.join().unwrap();
     }
 
-    assert_eq!(parser.parse("foo"), Ok(Value::Str("foo")));
+    assert_eq!(parser.parse("foo"), Ok(Value::Str("foo".into())));
 }
 
 #[test]
@@ -108,16 +124,556 @@ fn file() {
         parser.parse_file("examples/dent/dict.dent"),
         Ok(Value::Dict(
             vec![
-                ("name", Value::Str("Mario")),
+                ("name".into(), Value::Str("Mario".into())),
                 (
-                    "skills",
-                    Value::List(vec![Value::Str("jumps"), Value::Str("grows")])
+                    "skills".into(),
+                    Value::List(vec![Value::Str("jumps".into()), Value::Str("grows".into())])
                 ),
-                ("age", Value::Int(35)),
-                ("alive", Value::Bool(true)),
+                ("age".into(), Value::Int(35)),
+                ("alive".into(), Value::Bool(true)),
             ]
             .into_iter()
             .collect()
         ))
     );
 }
+
+#[test]
+fn error_location() {
+    let parser = Dent::new(HashMap::new());
+
+    assert_eq!(
+        parser.parse("{\n  foo: 1\n  bar 2\n}"),
+        Err(Error::MissingColon {
+            key: "bar".to_string(),
+            token: "NUMBER".to_string(),
+            location: Location::new(Span::new(17, 18, 3, 7)),
+        })
+    );
+    assert_eq!(
+        parser.parse("[ 1 2"),
+        Err(Error::UnexpectedEof {
+            location: Location::new(Span::new(5, 5, 1, 6)),
+        })
+    );
+    assert_eq!(
+        parser.parse("@nope 1"),
+        Err(Error::UnknownFunction {
+            name: "nope".to_string(),
+            location: Location::new(Span::new(1, 5, 1, 2)),
+        })
+    );
+}
+
+#[test]
+fn error_display() {
+    let parser = Dent::new(HashMap::new());
+
+    assert_eq!(
+        parser.parse("{ a: 1 b }").unwrap_err().to_string(),
+        "1:10: Expected `:` after dictionary key `b`, found `}`"
+    );
+}
+
+#[test]
+fn file_error_location() {
+    let parser = Dent::new(HashMap::new());
+
+    let error = parser.parse_file("examples/dent/broken.dent").unwrap_err();
+    let path = Path::new("examples/dent/broken.dent")
+        .canonicalize()
+        .unwrap();
+
+    assert_eq!(
+        error,
+        Error::MissingColon {
+            key: "age".to_string(),
+            token: "NUMBER".to_string(),
+            location: Location {
+                file: Some(Arc::from(path.as_path())),
+                span: Span::new(27, 29, 3, 9),
+            },
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "{}:3:9: Expected `:` after dictionary key `age`, found a number",
+            path.display()
+        )
+    );
+}
+
+#[test]
+fn escaped_strings() {
+    let parser = Dent::new(HashMap::new());
+
+    assert_eq!(
+        parser.parse(r#"{ "say \"hi\"": "line\nbreak" }"#),
+        Ok(Value::Dict(
+            vec![("say \"hi\"".into(), Value::Str("line\nbreak".into()))]
+                .into_iter()
+                .collect()
+        ))
+    );
+    assert_eq!(
+        parser.parse("[ \"\"\"\n  raw \\n\n\"\"\" ]"),
+        Ok(Value::List(vec![Value::Str("  raw \\n\n".into())]))
+    );
+}
+
+#[test]
+fn numbers() {
+    let parser = Dent::new(HashMap::new());
+
+    assert_eq!(parser.parse("-5"), Ok(Value::Int(-5)));
+    assert_eq!(parser.parse("+5"), Ok(Value::Int(5)));
+    assert_eq!(parser.parse("1_000_000"), Ok(Value::Int(1_000_000)));
+    assert_eq!(parser.parse("0xff"), Ok(Value::Int(255)));
+    assert_eq!(parser.parse("-0o17"), Ok(Value::Int(-15)));
+    assert_eq!(parser.parse("0b1010_1010"), Ok(Value::Int(170)));
+    assert_eq!(parser.parse("11."), Ok(Value::Float(11.0)));
+    assert_eq!(parser.parse("-2.5"), Ok(Value::Float(-2.5)));
+    assert_eq!(parser.parse("1e9"), Ok(Value::Float(1e9)));
+    assert_eq!(parser.parse("1.5E-3"), Ok(Value::Float(1.5e-3)));
+    assert_eq!(parser.parse("inf"), Ok(Value::Float(f64::INFINITY)));
+    assert_eq!(parser.parse("-inf"), Ok(Value::Float(f64::NEG_INFINITY)));
+    assert!(parser.parse("nan").unwrap().as_float().unwrap().is_nan());
+    assert!(parser.parse("[ +nan ]").unwrap()[0]
+        .as_float()
+        .unwrap()
+        .is_nan());
+    assert_eq!(parser.parse("\"inf\""), Ok(Value::Str("inf".into())));
+
+    // As keys, `inf` and `nan` are words like any other
+    let value = parser.parse("{ inf: 1 -inf: 2 nan: nan }").unwrap();
+    assert_eq!(
+        value.as_dict().unwrap().keys().collect::<Vec<_>>(),
+        vec!["inf", "-inf", "nan"]
+    );
+    assert_eq!(value["inf"], Value::Int(1));
+    assert!(value["nan"].as_float().unwrap().is_nan());
+    for source in ["{ 0xff: 1 }", "{ 12abc: 1 }"] {
+        assert!(
+            matches!(parser.parse(source), Err(Error::InvalidKey { .. })),
+            "{}",
+            source
+        );
+    }
+    assert_eq!(
+        parser.parse("{ 42: answer }"),
+        Err(Error::InvalidKey {
+            token: "NUMBER".to_string(),
+            location: Location::new(Span::new(2, 4, 1, 3)),
+        })
+    );
+    assert_eq!(
+        parser.parse("-9223372036854775808"),
+        Ok(Value::Int(i64::MIN))
+    );
+    assert_eq!(
+        parser.parse("[ 1 -2 info ]"),
+        Ok(Value::List(vec![
+            Value::Int(1),
+            Value::Int(-2),
+            Value::Str("info".into())
+        ]))
+    );
+}
+
+#[test]
+fn invalid_numbers() {
+    let parser = Dent::new(HashMap::new());
+
+    assert_eq!(
+        parser.parse("[ 1.2.3 ]"),
+        Err(Error::InvalidNumber {
+            literal: "1.2.3".to_string(),
+            location: Location::new(Span::new(2, 7, 1, 3)),
+        })
+    );
+    for literal in [
+        "1__0", "1_", "0x", "0xg", "1e", "1e+", "12abc", "0b102", "1._5",
+    ] {
+        assert!(
+            matches!(parser.parse(literal), Err(Error::InvalidNumber { .. })),
+            "{} should be invalid",
+            literal
+        );
+    }
+}
+
+#[test]
+fn number_overflow() {
+    let parser = Dent::new(HashMap::new());
+
+    assert_eq!(
+        parser.parse("9223372036854775808"),
+        Err(Error::NumberOverflow {
+            literal: "9223372036854775808".to_string(),
+            location: Location::new(Span::new(0, 19, 1, 1)),
+        })
+    );
+    for literal in ["0xffffffffffffffff", "-9223372036854775809", "1e999"] {
+        assert!(
+            matches!(parser.parse(literal), Err(Error::NumberOverflow { .. })),
+            "{} should overflow",
+            literal
+        );
+    }
+}
+
+#[test]
+fn duplicate_keys() {
+    let mut parser = Dent::new(HashMap::new());
+
+    assert_eq!(parser.parse("{ a: 1 a: 2 }").unwrap()["a"], Value::Int(2));
+
+    parser.set_duplicate_keys(DuplicateKeys::KeepFirst);
+    assert_eq!(
+        parser.parse("{ a: 1 b: 2 a: 3 }"),
+        Ok(Value::Dict(
+            vec![("a".into(), Value::Int(1)), ("b".into(), Value::Int(2))]
+                .into_iter()
+                .collect()
+        ))
+    );
+
+    parser.set_duplicate_keys(DuplicateKeys::Error);
+    assert_eq!(
+        parser.parse("{\n  a: 1\n  a: 2\n}"),
+        Err(Error::DuplicateKey {
+            key: "a".to_string(),
+            first: Location::new(Span::new(4, 5, 2, 3)),
+            location: Location::new(Span::new(11, 12, 3, 3)),
+        })
+    );
+}
+
+#[test]
+fn duplicate_keys_strict() {
+    let mut parser = Dent::new(HashMap::new());
+    parser.set_strict(true);
+
+    assert!(matches!(
+        parser.parse("[ { a: { b: 1 b: 2 } } ]"),
+        Err(Error::DuplicateKey { .. })
+    ));
+    assert_eq!(
+        parser.parse("[ { a: 1 } { a: 2 } ]"),
+        Ok(Value::List(vec![
+            Value::Dict(vec![("a".into(), Value::Int(1))].into_iter().collect()),
+            Value::Dict(vec![("a".into(), Value::Int(2))].into_iter().collect()),
+        ]))
+    );
+
+    parser.set_duplicate_keys(DuplicateKeys::KeepLast);
+    assert_eq!(parser.parse("{ a: 1 a: 2 }").unwrap()["a"], Value::Int(2));
+}
+
+#[test]
+fn duplicate_keys_warn() {
+    let mut parser = Dent::new(HashMap::new());
+    let warnings = Arc::new(Mutex::new(Vec::new()));
+
+    let w = warnings.clone();
+    parser.set_duplicate_keys(DuplicateKeys::Warn);
+    parser.set_diagnostic_handler(Box::new(move |d| w.lock().unwrap().push(d.clone())));
+
+    assert_eq!(parser.parse("{ a: 1 a: 2 }").unwrap()["a"], Value::Int(2));
+
+    let warnings = warnings.lock().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].render("{ a: 1 a: 2 }"),
+        "warning: duplicate dictionary key `a`\n \
+         --> 1:8\n  \
+         |\n\
+         1 | { a: 1 a: 2 }\n  \
+         |   - first defined here\n  \
+         |        ^ overrides the earlier value\n"
+    );
+}
+
+#[test]
+fn locations() {
+    let mut parser = Dent::new(HashMap::new());
+    parser.add_function("wrap", Box::new(|value| Value::List(vec![value.clone()])));
+
+    let (value, locations) = parser
+        .parse_with_locations("# hero\n{\n  name: Mario\n  skills: [ jumps @wrap grows ]\n}")
+        .unwrap();
+    assert_eq!(value["skills"][1][0], Value::Str("grows".into()));
+
+    let span = |path| locations.get(path).unwrap().span;
+    assert_eq!(span(""), Span::new(7, 8, 2, 1));
+    assert_eq!(span("name"), Span::new(17, 22, 3, 9));
+    assert_eq!(span("skills[0]"), Span::new(35, 40, 4, 13));
+    // Values returned by functions are located at the call
+    assert_eq!(span("skills[1]"), Span::new(41, 42, 4, 19));
+    assert_eq!(span("skills[1][0]"), span("skills[1]"));
+    assert_eq!(span("missing.key"), span(""));
+
+    // Keys that would make the path ambiguous are quoted
+    let (_, locations) = parser
+        .parse_with_locations("{ a: { b: 1 } \"a.b\": 2 \"x[\\\"]\": { c: 3 } \"\": 4 }")
+        .unwrap();
+    let span = |path| locations.get(path).unwrap().span;
+    assert_eq!(span("a.b").start, 10);
+    assert_eq!(span("[\"a.b\"]").start, 21);
+    assert_eq!(span("[\"x[\\\"]\"].c").start, 37);
+    assert_eq!(span("[\"x[\\\"]\"].missing"), span("[\"x[\\\"]\"]"));
+    assert_eq!(span("[\"a.b\"][0]"), span("[\"a.b\"]"));
+    assert_eq!(span("[\"\"]").start, 45);
+
+    let (_, locations) = Dent::default()
+        .parse_file_with_locations("examples/dent/dict.dent")
+        .unwrap();
+    let location = locations.get("age").unwrap();
+    assert!(location.file.as_ref().unwrap().ends_with("dict.dent"));
+    assert_eq!((location.span.line, location.span.column), (8, 10));
+}
+
+#[test]
+fn context_function() {
+    let mut parser = Dent::new(HashMap::new());
+    parser.add_context_function(
+        "where",
+        Box::new(|_, context| {
+            let file = context
+                .file()
+                .and_then(|f| f.file_name())
+                .map_or("<string>".to_string(), |f| f.to_string_lossy().into_owned());
+            Value::from(format!("{}:{}", file, context.location().span))
+        }),
+    );
+
+    assert_eq!(
+        parser.parse("\n  @where none"),
+        Ok(Value::from("<string>:2:4"))
+    );
+
+    let path = std::env::temp_dir().join(format!("dent-context-{}.dent", std::process::id()));
+    std::fs::write(&path, "[ @where none ]").unwrap();
+    let value = parser.parse_file(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(
+        value.unwrap()[0],
+        Value::from(format!("dent-context-{}.dent:1:4", std::process::id()))
+    );
+}
+
+#[test]
+fn import_errors() {
+    let dir = std::env::temp_dir().join(format!("dent-import-errors-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.dent"), "{ b: @import \"b.dent\" }").unwrap();
+    std::fs::write(dir.join("b.dent"), "[\n  @import \"broken.dent\"\n]").unwrap();
+    std::fs::write(dir.join("broken.dent"), "{ age 40 }").unwrap();
+    std::fs::write(dir.join("missing.dent"), "[ @import \"nothing.dent\" ]").unwrap();
+
+    let parser = Dent::default();
+    let error = parser.parse_file(dir.join("a.dent")).unwrap_err();
+    let result = parser.parse_file(dir.join("missing.dent"));
+    std::fs::remove_dir_all(&dir).unwrap();
+    let dir = dir.canonicalize().unwrap_or(dir);
+
+    // The chain of imports leads to the error in the last file
+    let (b_path, b_error, b_location) = match &error {
+        Error::ImportFailed {
+            path,
+            source,
+            location,
+        } => (path, source.as_ref(), location),
+        e => panic!("expected an import error, found {:?}", e),
+    };
+    assert!(b_path.ends_with("b.dent"));
+    assert_eq!(
+        b_location.file.as_deref(),
+        Some(dir.join("a.dent").as_path())
+    );
+    assert_eq!(b_location.span, Span::new(6, 12, 1, 7));
+
+    let (broken_error, broken_location) = match b_error {
+        Error::ImportFailed {
+            source, location, ..
+        } => (source.as_ref(), location),
+        e => panic!("expected an import error, found {:?}", e),
+    };
+    assert_eq!(broken_location.span.line, 2);
+    assert!(matches!(
+        broken_error,
+        Error::MissingColon { location, .. }
+            if location.file.as_deref() == Some(dir.join("broken.dent").as_path())
+    ));
+
+    // Rendered, the error points at the broken file, with where it was
+    // imported from as notes
+    let diagnostic = crate::diagnostics::Diagnostic::from(&error);
+    assert_eq!(
+        diagnostic.message,
+        "expected `:` after dictionary key `age`"
+    );
+    assert_eq!(diagnostic.secondary.len(), 2);
+    assert_eq!(diagnostic.secondary[1].location, *b_location);
+
+    assert!(matches!(
+        result,
+        Err(Error::ImportNotFound { path, .. }) if path == "nothing.dent"
+    ));
+    assert_eq!(
+        parser.parse_file("examples/dent/nothing.dent"),
+        Err(Error::Io(std::io::ErrorKind::NotFound))
+    );
+}
+
+#[test]
+fn import_cycles() {
+    let dir = std::env::temp_dir().join(format!("dent-import-cycles-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.dent"), "{ b: @import \"b.dent\" }").unwrap();
+    std::fs::write(dir.join("b.dent"), "{ a: @import \"a.dent\" }").unwrap();
+    std::fs::write(dir.join("self.dent"), "[ 1 @import \"self.dent\" ]").unwrap();
+    std::fs::write(
+        dir.join("tree.dent"),
+        "{ name: root child: @import { path: \"tree.dent\" recursive: true } }",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("options.dent"),
+        "@import { path: \"a.dent\" as: b }",
+    )
+    .unwrap();
+
+    let parser = Dent::default();
+    let a = parser.parse_file(dir.join("a.dent"));
+    let itself = parser.parse_file(dir.join("self.dent"));
+    let tree = parser.parse_file(dir.join("tree.dent"));
+    let options = parser.parse_file(dir.join("options.dent"));
+    std::fs::remove_dir_all(&dir).unwrap();
+    let dir = dir.canonicalize().unwrap_or(dir);
+
+    // `a.dent` imports `b.dent`, whose import of `a.dent` closes the cycle
+    let cycle = match a {
+        Err(Error::ImportFailed { path, source, .. }) if path.ends_with("b.dent") => *source,
+        r => panic!("expected an import error, found {:?}", r),
+    };
+    assert_eq!(
+        cycle,
+        Error::ImportCycle {
+            chain: vec![dir.join("a.dent"), dir.join("b.dent"), dir.join("a.dent")],
+            location: Location {
+                file: Some(dir.join("b.dent").into()),
+                span: Span::new(6, 12, 1, 7),
+            },
+        }
+    );
+    assert_eq!(
+        cycle.to_string().rsplit(": ").next(),
+        Some(
+            format!(
+                "{} -> {} -> {}",
+                dir.join("a.dent").display(),
+                dir.join("b.dent").display(),
+                dir.join("a.dent").display()
+            )
+            .as_str()
+        )
+    );
+    let diagnostic = crate::diagnostics::Diagnostic::from(&cycle);
+    assert_eq!(diagnostic.message, "import cycle");
+    assert_eq!(diagnostic.help.len(), 1);
+
+    assert!(matches!(
+        itself,
+        Err(Error::ImportCycle { chain, .. }) if chain.len() == 2
+    ));
+
+    // Marked as recursive, the import that closes the cycle is `none`
+    assert_eq!(
+        tree.map(|v| Emitter::compact().emit(&v)),
+        Ok("{ name: root child: none }".to_string())
+    );
+
+    assert!(matches!(
+        options,
+        Err(Error::FunctionFailed { name, message, .. })
+            if name == "import" && message == "unknown import option `as`"
+    ));
+}
+
+#[test]
+fn import_cycles_cached() {
+    let dir = std::env::temp_dir().join(format!("dent-import-cached-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.dent"), "{ b: @import \"b.dent\" }").unwrap();
+    std::fs::write(
+        dir.join("b.dent"),
+        "{ a: @import { path: \"a.dent\" recursive: true } }",
+    )
+    .unwrap();
+
+    // Each file reads the same whichever is parsed first, as the value of
+    // `b.dent` with the import of `a.dent` cut off is not cached
+    let is_cycle = |r: &Result<OwnedValue>| {
+        matches!(r, Err(Error::ImportFailed { source, .. })
+            if matches!(source.as_ref(), Error::ImportCycle { chain, .. } if chain.len() == 3))
+    };
+    let emit = |r: Result<OwnedValue>| r.map(|v| Emitter::compact().emit(&v));
+    for b_first in [false, true] {
+        let parser = Dent::default();
+        let (a_value, b_value) = if b_first {
+            let b_value = parser.parse_file(dir.join("b.dent"));
+            (parser.parse_file(dir.join("a.dent")), b_value)
+        } else {
+            let a_value = parser.parse_file(dir.join("a.dent"));
+            (a_value, parser.parse_file(dir.join("b.dent")))
+        };
+        assert_eq!(emit(a_value), Ok("{ b: { a: none } }".to_string()));
+        assert!(is_cycle(&b_value), "{:?}", b_value);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fallible_function() {
+    let mut parser = Dent::new(HashMap::new());
+    parser.add_fallible_function(
+        "first",
+        Box::new(|value, _| match value {
+            Value::List(items) if !items.is_empty() => Ok(items[0].clone()),
+            Value::List(_) => Err(FunctionError::new("the list is empty")),
+            v => Err(FunctionError::new(format!(
+                "expected a list, found {}",
+                v.type_name()
+            ))),
+        }),
+    );
+
+    assert_eq!(parser.parse("@first [ none 2 ]"), Ok(Value::None));
+    assert_eq!(
+        parser.parse("{\n  a: @first []\n}"),
+        Err(Error::FunctionFailed {
+            name: "first".to_string(),
+            message: "the list is empty".to_string(),
+            location: Location::new(Span::new(8, 13, 2, 7)),
+        })
+    );
+
+    let source = "[ @first 1 ]";
+    let error = parser.parse(source).unwrap_err();
+    assert_eq!(
+        crate::diagnostics::Diagnostic::from(&error).render(source),
+        "error: call to `first` failed\n \
+         --> 1:4\n  \
+         |\n\
+         1 | [ @first 1 ]\n  \
+         |    ^^^^^ expected a list, found int\n"
+    );
+
+    // Errors in the argument are returned before the function is called
+    assert!(matches!(
+        parser.parse("@first [ 1"),
+        Err(Error::UnexpectedEof { .. })
+    ));
+}
@@ -0,0 +1,283 @@
+use super::*;
+use crate::query::{Pipeline, Query};
+
+fn characters() -> Value<'static> {
+    Dent::default()
+        .parse(
+            r#"{
+                characters: [
+                    { name: Mario age: 35 alive: true skills: [ jumps grows ] }
+                    { name: Luigi age: 33 alive: true skills: [ jumps ] }
+                    { name: Bowser age: 40.5 alive: false }
+                    { name: "King Boo" }
+                ]
+                "game title": "Super Mario"
+            }"#,
+        )
+        .unwrap()
+}
+
+fn select(query: &str) -> Vec<Value<'static>> {
+    let value = characters();
+    Query::parse(query)
+        .unwrap()
+        .select(&value)
+        .into_iter()
+        .cloned()
+        .collect()
+}
+
+fn run(pipeline: &str) -> Vec<Value<'static>> {
+    Pipeline::parse(pipeline)
+        .unwrap()
+        .run(&characters())
+        .unwrap()
+}
+
+fn value(source: &str) -> Value<'static> {
+    Dent::default().parse(source).unwrap().into_owned()
+}
+
+fn strs(strs: &[&'static str]) -> Vec<Value<'static>> {
+    strs.iter().map(|s| Value::from(*s)).collect()
+}
+
+#[test]
+fn paths() {
+    assert_eq!(select("."), vec![characters()]);
+    assert_eq!(select(".characters[0].name"), strs(&["Mario"]));
+    assert_eq!(select("characters[1].skills[0]"), strs(&["jumps"]));
+    assert_eq!(select(".\"game title\""), strs(&["Super Mario"]));
+    assert_eq!(select("[\"game title\"]"), strs(&["Super Mario"]));
+    assert_eq!(select(".characters[9].name"), strs(&[]));
+    assert_eq!(select(".missing.key"), strs(&[]));
+}
+
+#[test]
+fn wildcards() {
+    assert_eq!(
+        select(".characters[*].name"),
+        strs(&["Mario", "Luigi", "Bowser", "King Boo"])
+    );
+    assert_eq!(select(".characters.*.name").len(), 4);
+    assert_eq!(select(".characters[0].*").len(), 4);
+}
+
+#[test]
+fn indices_and_slices() {
+    assert_eq!(select(".characters[-1].name"), strs(&["King Boo"]));
+    assert_eq!(select(".characters[-5].name"), strs(&[]));
+    assert_eq!(select(".characters[1:3].name"), strs(&["Luigi", "Bowser"]));
+    assert_eq!(select(".characters[:1].name"), strs(&["Mario"]));
+    assert_eq!(
+        select(".characters[-2:].name"),
+        strs(&["Bowser", "King Boo"])
+    );
+    assert_eq!(select(".characters[3:1].name"), strs(&[]));
+    assert_eq!(select(".characters[:].name").len(), 4);
+    assert_eq!(select(".characters[].name").len(), 4);
+}
+
+#[test]
+fn recursive_descent() {
+    assert_eq!(
+        select("..name"),
+        strs(&["Mario", "Luigi", "Bowser", "King Boo"])
+    );
+    assert_eq!(select("..skills[0]"), strs(&["jumps", "jumps"]));
+    assert_eq!(select("..[1]").len(), 2);
+    assert_eq!(select(".characters[0]..*").len(), 6);
+}
+
+#[test]
+fn filters() {
+    assert_eq!(
+        select(".characters[?(.age > 34)].name"),
+        strs(&["Mario", "Bowser"])
+    );
+    assert_eq!(
+        select(".characters[?(.age == 40.5)].name"),
+        strs(&["Bowser"])
+    );
+    assert_eq!(select(".characters[?(.age >= 35.0)].name").len(), 2);
+    assert_eq!(
+        select(".characters[?(.alive)].name"),
+        strs(&["Mario", "Luigi"])
+    );
+    assert_eq!(
+        select(".characters[?(!.alive)].name"),
+        strs(&["Bowser", "King Boo"])
+    );
+    assert_eq!(
+        select(".characters[?(.name == \"King Boo\" || .age < 34 && .alive)].name"),
+        strs(&["Luigi", "King Boo"])
+    );
+    assert_eq!(
+        select(".characters[?((.age > 30 || .alive) && .name != \"Mario\")].name"),
+        strs(&["Luigi", "Bowser"])
+    );
+    assert_eq!(select(".characters[?(@.skills[1])].name"), strs(&["Mario"]));
+    assert_eq!(
+        select(".characters[?(.age == none)].name"),
+        strs(&["King Boo"])
+    );
+    assert_eq!(select("..skills[?(@ == \"grows\")]"), strs(&["grows"]));
+}
+
+#[test]
+fn invalid() {
+    for (query, offset) in [
+        (".characters[", 12),
+        (".characters[0", 13),
+        (".characters[?(.age >)]", 20),
+        (".characters[abc]", 12),
+        (".a.", 3),
+        (".a b", 3),
+        (".a[?(.b == \"x)]", 11),
+        (".a[?(@ == grows)]", 10),
+    ] {
+        match Query::parse(query) {
+            Err(Error::InvalidQuery { location, .. }) => {
+                assert_eq!(location.span.start, offset, "{}", query)
+            }
+            other => panic!("{}: {:?}", query, other),
+        }
+    }
+}
+
+#[test]
+fn pipelines() {
+    assert_eq!(run(".characters[0] | .name"), strs(&["Mario"]));
+    assert_eq!(run("characters[0].name"), strs(&["Mario"]));
+    assert_eq!(
+        run(".characters[0] | .name, .age"),
+        vec![value("Mario"), value("35")]
+    );
+    assert_eq!(run("[.characters[].age]"), vec![value("[ 35 33 40.5 ]")]);
+    assert_eq!(run("(.characters | first).skills[1]"), strs(&["grows"]));
+    assert_eq!(
+        run(".characters[0] | { name, years: .age }"),
+        vec![value("{ name: Mario years: 35 }")]
+    );
+    assert_eq!(
+        run("{ name: .characters[0:2].name }"),
+        vec![value("{ name: Mario }"), value("{ name: Luigi }")]
+    );
+    assert_eq!(run("[]"), vec![value("[]")]);
+    assert_eq!(run(".missing | length"), strs(&[]));
+}
+
+#[test]
+fn pipeline_functions() {
+    assert_eq!(run(".characters | map(.name) | length"), vec![value("4")]);
+    assert_eq!(
+        run(".characters[0] | keys"),
+        vec![value("[ name age alive skills ]")]
+    );
+    assert_eq!(run(".characters[0].name | length"), vec![value("5")]);
+    assert_eq!(
+        run(".characters[] | select(.alive) | .name"),
+        strs(&["Mario", "Luigi"])
+    );
+    assert_eq!(
+        run(".characters | sort_by(.age) | map(.name)"),
+        vec![value("[ \"King Boo\" Luigi Mario Bowser ]")]
+    );
+    assert_eq!(
+        run(".characters | group_by(.alive) | map(length)"),
+        vec![value("[ 1 1 2 ]")]
+    );
+    assert_eq!(
+        run("[..skills] | add"),
+        vec![value("[ jumps grows jumps ]")]
+    );
+    assert_eq!(run("[..skills[]] | unique"), vec![value("[ grows jumps ]")]);
+    assert_eq!(run("[..age] | max"), vec![value("40.5")]);
+    assert_eq!(
+        run(".characters[0] | has(\"age\"), has(\"x\")"),
+        vec![value("true"), value("false")]
+    );
+    assert_eq!(
+        run(".characters[3] | .name, none | type"),
+        strs(&["string", "none"])
+    );
+    assert_eq!(
+        run(".characters | map(.alive | not)"),
+        vec![value("[ false false true ]")]
+    );
+}
+
+#[test]
+fn pipeline_operators() {
+    assert_eq!(run("1 + 2 * 3"), vec![value("7")]);
+    assert_eq!(run("(1 + 2) * 3 - -1"), vec![value("10")]);
+    assert_eq!(
+        run("7 / 2, 6 / 2, 7 % 2"),
+        vec![value("3.5"), value("3"), value("1")]
+    );
+    assert_eq!(run(".characters[2].age * 2"), vec![value("81.0")]);
+    assert_eq!(run("\"a\" + \"b\""), strs(&["ab"]));
+    assert_eq!(run("[1, 2, 3] - [2]"), vec![value("[ 1 3 ]")]);
+    assert_eq!(run("{ a: 1 } + { b: 2 }"), vec![value("{ a: 1 b: 2 }")]);
+    assert_eq!(
+        run(".characters[] | select(.age > 34 and .alive) | .name"),
+        strs(&["Mario"])
+    );
+    assert_eq!(
+        run("1 < 2 or false, !true"),
+        vec![value("true"), value("false")]
+    );
+    assert_eq!(
+        run(".characters[] | select(.alive and (.age > 34 | not)) | .name"),
+        strs(&["Luigi"])
+    );
+    assert_eq!(
+        run("(true | not), !(.characters | length > 3)"),
+        vec![value("false"), value("false")]
+    );
+}
+
+#[test]
+fn pipeline_errors() {
+    for (pipeline, offset) in [
+        (".characters[0].name + 1", 20),
+        (".characters | sort_by(.age) | keys | add | . / 0", 45),
+        (".characters[0] | sort", 17),
+        ("-\"a\"", 0),
+    ] {
+        match Pipeline::parse(pipeline).unwrap().run(&characters()) {
+            Err(Error::QueryFailed { location, .. }) => {
+                assert_eq!(location.span.start, offset, "{}", pipeline)
+            }
+            other => panic!("{}: {:?}", pipeline, other),
+        }
+    }
+
+    let min = value("{ x: -9223372036854775808 }");
+    for pipeline in [".x / -1", ".x % -1", ".x - 1"] {
+        match Pipeline::parse(pipeline).unwrap().run(&min) {
+            Err(Error::QueryFailed { message, .. }) => {
+                assert_eq!(message, "integer overflow", "{}", pipeline)
+            }
+            other => panic!("{}: {:?}", pipeline, other),
+        }
+    }
+
+    // `not` is a function, not a prefix operator
+    for pipeline in ["not .a", ".a and not .b"] {
+        assert!(
+            matches!(Pipeline::parse(pipeline), Err(Error::InvalidQuery { .. })),
+            "{}",
+            pipeline
+        );
+    }
+
+    for (pipeline, offset) in [(".a |", 4), ("map(.a", 6), ("foo(.a)", 0), ("{ a: }", 5)] {
+        match Pipeline::parse(pipeline) {
+            Err(Error::InvalidQuery { location, .. }) => {
+                assert_eq!(location.span.start, offset, "{}", pipeline)
+            }
+            other => panic!("{}: {:?}", pipeline, other),
+        }
+    }
+}
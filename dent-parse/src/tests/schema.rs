@@ -0,0 +1,357 @@
+use super::*;
+use crate::schema::{Schema, Violation};
+
+fn schema(source: &str) -> Schema {
+    Schema::from_value(&Dent::default().parse(source).unwrap()).unwrap()
+}
+
+/// Validates `source`, returning the violations as `path: message` strings.
+fn violations(schema: &Schema, source: &str) -> Vec<String> {
+    schema
+        .validate(&Dent::default().parse(source).unwrap())
+        .iter()
+        .map(Violation::to_string)
+        .collect()
+}
+
+#[test]
+fn types() {
+    let int = schema("int");
+    assert!(violations(&int, "1").is_empty());
+    assert_eq!(violations(&int, "1.5"), vec!["expected int, found float"]);
+
+    let number = schema("{ type: number }");
+    assert!(violations(&number, "1").is_empty());
+    assert!(violations(&number, "1.5").is_empty());
+    assert_eq!(
+        violations(&number, "one"),
+        vec!["expected number, found string"]
+    );
+
+    let optional = schema("{ type: [ string none ] }");
+    assert!(violations(&optional, "none").is_empty());
+    assert_eq!(
+        violations(&optional, "[]"),
+        vec!["expected string or none, found list"]
+    );
+
+    assert!(violations(&schema("any"), "{ a: 1 }").is_empty());
+    assert!(violations(&schema("{}"), "[ 1 ]").is_empty());
+}
+
+#[test]
+fn values() {
+    let mode = schema("{ enum: [ fast slow 0 ] }");
+    assert!(violations(&mode, "slow").is_empty());
+    assert!(violations(&mode, "0").is_empty());
+    assert_eq!(
+        violations(&mode, "\"very fast\""),
+        vec!["expected one of fast, slow or 0, found \"very fast\""]
+    );
+
+    let port = schema("{ type: int min: 1 max: 65535 }");
+    assert!(violations(&port, "8080").is_empty());
+    assert_eq!(violations(&port, "0"), vec!["expected at least 1, found 0"]);
+    assert_eq!(
+        violations(&port, "65536"),
+        vec!["expected at most 65535, found 65536"]
+    );
+
+    let ratio = schema("{ type: number min: 0 max: 0.5 }");
+    assert!(violations(&ratio, "0").is_empty());
+    assert_eq!(
+        violations(&ratio, "1"),
+        vec!["expected at most 0.5, found 1"]
+    );
+    assert_eq!(
+        violations(&ratio, "-0.1"),
+        vec!["expected at least 0, found -0.1"]
+    );
+}
+
+#[test]
+fn lengths_and_patterns() {
+    let tags = schema("{ type: list items: string min_length: 1 max_length: 2 }");
+    assert!(violations(&tags, "[ a b ]").is_empty());
+    assert_eq!(
+        violations(&tags, "[]"),
+        vec!["expected at least 1 item, found 0"]
+    );
+    assert_eq!(
+        violations(&tags, "[ a 2 c ]"),
+        vec![
+            "expected at most 2 items, found 3",
+            "[1]: expected string, found int"
+        ]
+    );
+
+    let code = schema("{ type: string pattern: \"^[A-Z]{3}$\" max_length: 3 }");
+    assert!(violations(&code, "ABC").is_empty());
+    assert_eq!(
+        violations(&code, "ABCD"),
+        vec![
+            "expected at most 3 characters, found 4",
+            "`ABCD` does not match the pattern `^[A-Z]{3}$`"
+        ]
+    );
+}
+
+#[test]
+fn dictionaries() {
+    let server = schema(
+        "{
+            keys: {
+                host: { type: string required: true }
+                port: int
+                tls: { keys: { cert: { type: string required: true } } }
+            }
+        }",
+    );
+    assert!(violations(&server, "{ host: localhost extra: 1 }").is_empty());
+    assert_eq!(
+        violations(&server, "{ port: http tls: {} }"),
+        vec![
+            "host: the required key is missing",
+            "port: expected int, found string",
+            "tls.cert: the required key is missing",
+        ]
+    );
+    assert_eq!(
+        violations(&server, "[ localhost ]"),
+        vec!["expected dict, found list"]
+    );
+
+    let closed = schema("{ keys: { a: int } additional_keys: false }");
+    assert_eq!(
+        violations(&closed, "{ a: 1 b: 2 }"),
+        vec!["b: the key is not allowed by the schema"]
+    );
+
+    let env = schema("{ additional_keys: string }");
+    assert_eq!(
+        violations(&env, "{ HOME: /root DEBUG: 1 }"),
+        vec!["DEBUG: expected string, found int"]
+    );
+}
+
+#[test]
+fn locations() {
+    let schema = Schema::from_value(
+        &Dent::default()
+            .parse_file("examples/dent/person.schema.dent")
+            .unwrap(),
+    )
+    .unwrap();
+
+    let source = "{\n  name: luigi\n  skills: [ jumps runs ]\n  age: -1\n  alive: yes\n}";
+    let (value, locations) = Dent::default().parse_with_locations(source).unwrap();
+    let violations = schema.validate_located(&value, &locations);
+
+    assert_eq!(
+        violations
+            .iter()
+            .map(|v| (v.to_string(), v.location.as_ref().unwrap().span.line))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                "name: `luigi` does not match the pattern `^[A-Z]`".to_string(),
+                2
+            ),
+            (
+                "skills[1]: expected one of jumps, grows, flies or swims, found runs".to_string(),
+                3
+            ),
+            ("age: expected at least 0, found -1".to_string(), 4),
+            ("alive: expected bool, found string".to_string(), 5),
+        ]
+    );
+
+    assert_eq!(
+        crate::diagnostics::Diagnostic::from(&violations[2]).render(source),
+        "error: age: expected at least 0, found -1\n \
+         --> 4:8\n  \
+         |\n\
+         4 |   age: -1\n  \
+         |        ^^ expected at least 0, found -1\n"
+    );
+
+    let (value, locations) = Dent::default()
+        .parse_file_with_locations("examples/dent/dict.dent")
+        .unwrap();
+    assert!(schema.validate_located(&value, &locations).is_empty());
+
+    // A dotted key is not confused with a nested one
+    let schema = Schema::from_value(
+        &Dent::default()
+            .parse("{ keys: { \"a.b\": { type: string } a: { keys: { b: { type: int } } } } }")
+            .unwrap(),
+    )
+    .unwrap();
+    let source = "{\n  a: { b: x }\n  \"a.b\": 1\n}";
+    let (value, locations) = Dent::default().parse_with_locations(source).unwrap();
+    assert_eq!(
+        schema
+            .validate_located(&value, &locations)
+            .iter()
+            .map(|v| (v.to_string(), v.location.as_ref().unwrap().span.line))
+            .collect::<Vec<_>>(),
+        vec![
+            ("a.b: expected int, found string".to_string(), 2),
+            ("[\"a.b\"]: expected string, found int".to_string(), 3),
+        ]
+    );
+}
+
+#[test]
+fn invalid_schemas() {
+    let invalid = |source| Schema::from_value(&Dent::default().parse(source).unwrap()).unwrap_err();
+
+    assert_eq!(
+        invalid("{ keys: { port: { type: integer } } }"),
+        Error::InvalidSchema {
+            path: "keys.port.type".to_string(),
+            message: "unknown type `integer`, expected none, string, int, float, number, \
+                      bool, list, dict or any"
+                .to_string(),
+        }
+    );
+    assert_eq!(
+        invalid("{ items: { minimum: 1 } }").to_string(),
+        "Invalid schema: items.minimum: unknown schema key `minimum`"
+    );
+    assert_eq!(
+        invalid("{ min_length: -1 }").to_string(),
+        "Invalid schema: min_length: expected a non-negative int, found int"
+    );
+    assert_eq!(
+        invalid("{ type: [ int 1 ] }").to_string(),
+        "Invalid schema: type[1]: expected a type name, found int"
+    );
+    assert!(matches!(
+        invalid("{ pattern: \"[a-\" }"),
+        Error::InvalidSchema { path, .. } if path == "pattern"
+    ));
+    assert_eq!(
+        invalid("1").to_string(),
+        "Invalid schema: expected a type name or a dictionary, found int"
+    );
+}
+
+fn normalize(schema: &Schema, source: &str) -> std::result::Result<OwnedValue, Vec<String>> {
+    schema
+        .normalize(&Dent::default().parse(source).unwrap())
+        .map_err(|violations| violations.iter().map(Violation::to_string).collect())
+}
+
+fn dent(source: &str) -> OwnedValue {
+    Dent::default().parse(source).unwrap().into_owned()
+}
+
+#[test]
+fn defaults() {
+    let server = schema(
+        "{
+            keys: {
+                host: { type: string default: localhost }
+                port: { type: int default: 8080 }
+                tls: { keys: { enabled: { type: bool default: false } } default: {} }
+            }
+        }",
+    );
+
+    assert_eq!(
+        normalize(&server, "{ port: 80 }"),
+        Ok(dent("{ port: 80 host: localhost tls: { enabled: false } }"))
+    );
+    assert_eq!(
+        normalize(
+            &server,
+            "{ tls: { enabled: true } host: \"example.com\" port: 443 }"
+        ),
+        Ok(dent(
+            "{ tls: { enabled: true } host: \"example.com\" port: 443 }"
+        ))
+    );
+    // Validating does not fill in defaults, and missing keys with a default
+    // are not violations
+    assert!(violations(&server, "{}").is_empty());
+    assert_eq!(
+        normalize(&server, "{ port: http }"),
+        Err(vec!["port: expected int, found string".to_string()])
+    );
+}
+
+#[test]
+fn coercion() {
+    let config = schema(
+        "{
+            keys: {
+                speed: { type: float min: 0 }
+                weight: { type: [ float string ] }
+                count: number
+                tags: { type: list items: string }
+                hosts: { type: list items: { keys: { port: float } } }
+            }
+        }",
+    );
+
+    assert_eq!(
+        normalize(&config, "{ speed: 2 weight: 3 count: 4 tags: web }"),
+        Ok(Value::Dict(
+            vec![
+                ("speed".into(), Value::Float(2.0)),
+                ("weight".into(), Value::Float(3.0)),
+                ("count".into(), Value::Int(4)),
+                ("tags".into(), Value::List(vec![Value::Str("web".into())])),
+            ]
+            .into_iter()
+            .collect()
+        ))
+    );
+    assert_eq!(
+        normalize(&config, "{ hosts: { port: 80 } }").unwrap()["hosts"],
+        Value::List(vec![Value::Dict(
+            vec![("port".into(), Value::Float(80.0))]
+                .into_iter()
+                .collect()
+        )])
+    );
+
+    // Coerced values are checked like any other
+    assert_eq!(
+        normalize(&config, "{ speed: -1 tags: 1 }"),
+        Err(vec![
+            "speed: expected at least 0, found -1.0".to_string(),
+            "tags[0]: expected string, found int".to_string(),
+        ])
+    );
+    assert_eq!(
+        normalize(&config, "{ tags: none }"),
+        Err(vec!["tags: expected list, found none".to_string()])
+    );
+    // Validating does not coerce
+    assert_eq!(
+        violations(&config, "{ speed: 2 }"),
+        vec!["speed: expected float, found int"]
+    );
+}
+
+#[test]
+fn invalid_defaults() {
+    let invalid = |source| Schema::from_value(&Dent::default().parse(source).unwrap()).unwrap_err();
+
+    assert_eq!(
+        invalid("{ keys: { port: { type: int default: http } } }").to_string(),
+        "Invalid schema: keys.port.default: expected int, found string"
+    );
+    assert_eq!(
+        invalid("{ keys: { port: { type: int required: true default: 80 } } }").to_string(),
+        "Invalid schema: keys.port.default: a required key cannot have a default"
+    );
+    // Defaults are coerced too
+    let schema = schema("{ keys: { ratio: { type: float default: 1 } } }");
+    assert_eq!(
+        normalize(&schema, "{}").unwrap()["ratio"],
+        Value::Float(1.0)
+    );
+}
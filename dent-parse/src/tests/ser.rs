@@ -0,0 +1,157 @@
+use super::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum Power {
+    Mushroom,
+    Star(u32),
+    Flower { color: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Character {
+    name: String,
+    age: u32,
+    height: f64,
+    powers: Vec<Power>,
+    nickname: Option<String>,
+}
+
+fn mario() -> Character {
+    Character {
+        name: "Mario".into(),
+        age: 35,
+        height: 155.0,
+        powers: vec![
+            Power::Mushroom,
+            Power::Star(10),
+            Power::Flower {
+                color: "fire".into(),
+            },
+        ],
+        nickname: None,
+    }
+}
+
+#[test]
+fn compact() {
+    assert_eq!(
+        to_string(&mario()).unwrap(),
+        "{ name: Mario age: 35 height: 155.0 powers: [ Mushroom { Star: 10 } { Flower: { color: fire } } ] nickname: none }"
+    );
+}
+
+#[test]
+fn pretty() {
+    assert_eq!(
+        to_string_pretty(&mario()).unwrap(),
+        r#"{
+    name: Mario
+    age: 35
+    height: 155.0
+    powers: [ Mushroom { Star: 10 } { Flower: { color: fire } } ]
+    nickname: none
+}
+"#
+    );
+}
+
+#[test]
+fn round_trip() {
+    let text = to_string(&mario()).unwrap();
+    assert_eq!(from_str::<Character>(&text).unwrap(), mario());
+
+    let text = to_string_pretty(&mario()).unwrap();
+    assert_eq!(from_str::<Character>(&text).unwrap(), mario());
+}
+
+#[test]
+fn quoting() {
+    let strings = [
+        "plain",
+        "snake_case",
+        "-flag",
+        "./path",
+        "",
+        "hello world",
+        "true",
+        "false",
+        "none",
+        "inf",
+        "-nan",
+        "42",
+        "-1",
+        "1abc",
+        "a:b",
+        "#comment",
+        "@import",
+        "[",
+        "a-b",
+        "quote\"and\\backslash",
+        "line\nbreak\ttab\0\u{7}",
+    ];
+
+    let text = to_string(&strings).unwrap();
+    assert!(text.starts_with("[ plain snake_case -flag \"./path\" \"\" \"hello world\" \"true\""));
+    assert_eq!(from_str::<Vec<String>>(&text).unwrap(), strings);
+}
+
+#[test]
+fn floats() {
+    let floats = [1.0, -0.5, 1e300, f64::INFINITY, f64::NEG_INFINITY];
+    let text = to_string(&floats).unwrap();
+
+    assert_eq!(text, "[ 1.0 -0.5 1e300 inf -inf ]");
+    assert_eq!(from_str::<Vec<f64>>(&text).unwrap(), floats);
+    assert!(from_str::<f64>(&to_string(&f64::NAN).unwrap())
+        .unwrap()
+        .is_nan());
+}
+
+#[test]
+fn value() {
+    let value = Dent::default()
+        .parse("{ b: [ 1 2.5 ] a: { c: none } }")
+        .unwrap();
+
+    assert_eq!(to_value(&value).unwrap(), value);
+    assert_eq!(
+        to_string(&value).unwrap(),
+        "{ b: [ 1 2.5 ] a: { c: none } }"
+    );
+}
+
+#[test]
+fn map_keys() {
+    use std::collections::BTreeMap;
+
+    let map: BTreeMap<i32, bool> = [(1, true), (2, false)].into();
+
+    assert_eq!(to_string(&map).unwrap(), "{ \"1\": true \"2\": false }");
+    // Integer and boolean keys are parsed back
+    assert_eq!(
+        from_str::<BTreeMap<i32, bool>>(&to_string(&map).unwrap()).unwrap(),
+        map
+    );
+
+    let flags: BTreeMap<bool, u32> = [(false, 0), (true, 1)].into();
+    assert_eq!(
+        from_value::<BTreeMap<bool, u32>>(&to_value(&flags).unwrap()).unwrap(),
+        flags
+    );
+
+    assert_eq!(
+        from_str::<BTreeMap<u8, bool>>("{ \"1\": true \"256\": false }")
+            .unwrap_err()
+            .to_string(),
+        "256: invalid value: string \"256\", expected u8"
+    );
+}
+
+#[test]
+fn out_of_range() {
+    assert_eq!(
+        to_value(&u64::MAX).unwrap_err().to_string(),
+        "integer 18446744073709551615 is out of range"
+    );
+}
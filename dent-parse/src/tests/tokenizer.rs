@@ -1,4 +1,5 @@
 use super::*;
+use std::borrow::Cow;
 
 #[test]
 fn number() {
@@ -24,16 +25,16 @@ fn numbers() {
 #[test]
 fn string() {
     let mut tokenizer = Tokenizer::new("hello");
-    assert_eq!(tokenizer.next(), Ok(Token::String("hello")));
+    assert_eq!(tokenizer.next(), Ok(Token::String("hello".into())));
     assert_eq!(tokenizer.next(), Ok(Token::Eof));
 }
 
 #[test]
 fn strings() {
     let mut tokenizer = Tokenizer::new("hello \"dear\" world");
-    assert_eq!(tokenizer.next(), Ok(Token::String("hello")));
-    assert_eq!(tokenizer.next(), Ok(Token::String("dear")));
-    assert_eq!(tokenizer.next(), Ok(Token::String("world")));
+    assert_eq!(tokenizer.next(), Ok(Token::String("hello".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::String("dear".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::String("world".into())));
     assert_eq!(tokenizer.next(), Ok(Token::Eof));
 }
 
@@ -45,6 +46,15 @@ fn bool() {
     assert_eq!(tokenizer.next(), Ok(Token::Eof));
 }
 
+#[test]
+fn none() {
+    let mut tokenizer = Tokenizer::new("none \"none\" nones");
+    assert_eq!(tokenizer.next(), Ok(Token::None));
+    assert_eq!(tokenizer.next(), Ok(Token::String("none".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::String("nones".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::Eof));
+}
+
 #[test]
 fn special_characters() {
     let mut tokenizer = Tokenizer::new("[]{}@:");
@@ -60,7 +70,7 @@ fn special_characters() {
 #[test]
 fn comments() {
     let mut tokenizer = Tokenizer::new("hello # world\n");
-    assert_eq!(tokenizer.next(), Ok(Token::String("hello")));
+    assert_eq!(tokenizer.next(), Ok(Token::String("hello".into())));
     assert_eq!(tokenizer.next(), Ok(Token::Comment));
     assert_eq!(tokenizer.next(), Ok(Token::Eof));
 }
@@ -68,7 +78,7 @@ fn comments() {
 #[test]
 fn comments2() {
     let mut tokenizer = Tokenizer::new("hello # world\n# comment");
-    assert_eq!(tokenizer.next(), Ok(Token::String("hello")));
+    assert_eq!(tokenizer.next(), Ok(Token::String("hello".into())));
     assert_eq!(tokenizer.next(), Ok(Token::Comment));
     assert_eq!(tokenizer.next(), Ok(Token::Comment));
     assert_eq!(tokenizer.next(), Ok(Token::Eof));
@@ -86,7 +96,7 @@ fn list() {
     assert_eq!(tokenizer.next(), Ok(Token::OpenBracket));
     assert_eq!(tokenizer.next(), Ok(Token::Number("1")));
     assert_eq!(tokenizer.next(), Ok(Token::Number("2")));
-    assert_eq!(tokenizer.next(), Ok(Token::String("a")));
+    assert_eq!(tokenizer.next(), Ok(Token::String("a".into())));
     assert_eq!(tokenizer.next(), Ok(Token::CloseBracket));
     assert_eq!(tokenizer.next(), Ok(Token::Eof));
 }
@@ -95,12 +105,173 @@ fn list() {
 fn dict() {
     let mut tokenizer = Tokenizer::new("{a: 1 b: 2}");
     assert_eq!(tokenizer.next(), Ok(Token::OpenBrace));
-    assert_eq!(tokenizer.next(), Ok(Token::String("a")));
+    assert_eq!(tokenizer.next(), Ok(Token::String("a".into())));
     assert_eq!(tokenizer.next(), Ok(Token::Colon));
     assert_eq!(tokenizer.next(), Ok(Token::Number("1")));
-    assert_eq!(tokenizer.next(), Ok(Token::String("b")));
+    assert_eq!(tokenizer.next(), Ok(Token::String("b".into())));
     assert_eq!(tokenizer.next(), Ok(Token::Colon));
     assert_eq!(tokenizer.next(), Ok(Token::Number("2")));
     assert_eq!(tokenizer.next(), Ok(Token::CloseBrace));
     assert_eq!(tokenizer.next(), Ok(Token::Eof));
 }
+
+#[test]
+fn spans() {
+    let mut tokenizer = Tokenizer::new("{a: 12\n  \"b\"}");
+    assert_eq!(tokenizer.next(), Ok(Token::OpenBrace));
+    assert_eq!(tokenizer.span(), Span::new(0, 1, 1, 1));
+    assert_eq!(tokenizer.next(), Ok(Token::String("a".into())));
+    assert_eq!(tokenizer.span(), Span::new(1, 2, 1, 2));
+    assert_eq!(tokenizer.next(), Ok(Token::Colon));
+    assert_eq!(tokenizer.span(), Span::new(2, 3, 1, 3));
+    assert_eq!(tokenizer.next(), Ok(Token::Number("12")));
+    assert_eq!(tokenizer.span(), Span::new(4, 6, 1, 5));
+    assert_eq!(tokenizer.next(), Ok(Token::String("b".into())));
+    assert_eq!(tokenizer.span(), Span::new(9, 12, 2, 3));
+    assert_eq!(tokenizer.next(), Ok(Token::CloseBrace));
+    assert_eq!(tokenizer.span(), Span::new(12, 13, 2, 6));
+    assert_eq!(tokenizer.next(), Ok(Token::Eof));
+    assert_eq!(tokenizer.span(), Span::new(13, 13, 2, 7));
+}
+
+#[test]
+fn unexpected_char() {
+    let mut tokenizer = Tokenizer::new("a\n  ;");
+    assert_eq!(tokenizer.next(), Ok(Token::String("a".into())));
+    assert_eq!(
+        tokenizer.next(),
+        Err(Error::UnexpectedChar {
+            char: ';',
+            location: Location::new(Span::new(4, 5, 2, 3)),
+        })
+    );
+}
+
+#[test]
+fn escapes() {
+    let mut tokenizer = Tokenizer::new(r#""a\"b" "c\\d" "\n\t\r\0" "\u{1F986}\u{e9}""#);
+    assert_eq!(tokenizer.next(), Ok(Token::String("a\"b".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::String("c\\d".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::String("\n\t\r\0".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::String("🦆é".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::Eof));
+}
+
+#[test]
+fn zero_copy_strings() {
+    let mut tokenizer = Tokenizer::new(r#""plain" "esc\n""#);
+    assert!(matches!(
+        tokenizer.next(),
+        Ok(Token::String(Cow::Borrowed("plain")))
+    ));
+    assert!(matches!(tokenizer.next(), Ok(Token::String(Cow::Owned(_)))));
+}
+
+#[test]
+fn invalid_escapes() {
+    let mut tokenizer = Tokenizer::new(r#""a\qb""#);
+    assert_eq!(
+        tokenizer.next(),
+        Err(Error::InvalidEscape {
+            sequence: "\\q".to_string(),
+            location: Location::new(Span::new(2, 4, 1, 3)),
+        })
+    );
+
+    let mut tokenizer = Tokenizer::new(r#""\u{110000}""#);
+    assert!(matches!(tokenizer.next(), Err(Error::InvalidEscape { .. })));
+
+    let mut tokenizer = Tokenizer::new(r#""\u{}""#);
+    assert!(matches!(tokenizer.next(), Err(Error::InvalidEscape { .. })));
+}
+
+#[test]
+fn unterminated_string() {
+    let mut tokenizer = Tokenizer::new("a \"bc");
+    assert_eq!(tokenizer.next(), Ok(Token::String("a".into())));
+    assert_eq!(
+        tokenizer.next(),
+        Err(Error::UnterminatedString {
+            location: Location::new(Span::new(2, 3, 1, 3)),
+        })
+    );
+
+    let mut tokenizer = Tokenizer::new("\"ab\\");
+    assert!(matches!(
+        tokenizer.next(),
+        Err(Error::UnterminatedString { .. })
+    ));
+
+    let mut tokenizer = Tokenizer::new("\"\"\"ab\"\"");
+    assert_eq!(
+        tokenizer.next(),
+        Err(Error::UnterminatedString {
+            location: Location::new(Span::new(0, 3, 1, 1)),
+        })
+    );
+}
+
+#[test]
+fn raw_strings() {
+    let mut tokenizer =
+        Tokenizer::new("\"\"\"\nvoid main() {\n    \"\\n\"\n}\n\"\"\" \"\"\"x\"\"\" \"\"");
+    assert_eq!(
+        tokenizer.next(),
+        Ok(Token::String("void main() {\n    \"\\n\"\n}\n".into()))
+    );
+    assert_eq!(tokenizer.next(), Ok(Token::String("x".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::String("".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::Eof));
+}
+
+#[test]
+fn signed_numbers() {
+    let mut tokenizer = Tokenizer::new("-5 +1.5 1e-9 -2E+3 0xff_ff -inf nan - -foo +");
+    assert_eq!(tokenizer.next(), Ok(Token::Number("-5")));
+    assert_eq!(tokenizer.next(), Ok(Token::Number("+1.5")));
+    assert_eq!(tokenizer.next(), Ok(Token::Number("1e-9")));
+    assert_eq!(tokenizer.next(), Ok(Token::Number("-2E+3")));
+    assert_eq!(tokenizer.next(), Ok(Token::Number("0xff_ff")));
+    assert_eq!(tokenizer.next(), Ok(Token::Number("-inf")));
+    assert_eq!(tokenizer.next(), Ok(Token::Number("nan")));
+    assert_eq!(tokenizer.next(), Ok(Token::String("-".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::String("-foo".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::String("+".into())));
+    assert_eq!(tokenizer.next(), Ok(Token::Eof));
+}
+
+#[test]
+fn keys() {
+    let mut tokenizer = Tokenizer::new("name \"a b\" none -inf nan 0xff 12abc 42 true");
+    let mut keys = Vec::new();
+    loop {
+        let token = tokenizer.next().unwrap();
+        if token == Token::Eof {
+            break;
+        }
+        keys.push(token.key());
+    }
+    assert_eq!(
+        keys,
+        vec![
+            Some("name".into()),
+            Some("a b".into()),
+            Some("none".into()),
+            Some("-inf".into()),
+            Some("nan".into()),
+            None,
+            None,
+            None,
+            None,
+        ]
+    );
+}
+
+#[test]
+fn hex_exponent_sign() {
+    let mut tokenizer = Tokenizer::new("[0x1e-5]");
+    assert_eq!(tokenizer.next(), Ok(Token::OpenBracket));
+    assert_eq!(tokenizer.next(), Ok(Token::Number("0x1e")));
+    assert_eq!(tokenizer.next(), Ok(Token::Number("-5")));
+    assert_eq!(tokenizer.next(), Ok(Token::CloseBracket));
+}
@@ -0,0 +1,113 @@
+use super::*;
+use crate::toml;
+
+fn dent(source: &str) -> OwnedValue {
+    Dent::default().parse(source).unwrap().into_owned()
+}
+
+#[test]
+fn from_toml() {
+    let converted = toml::from_str(
+        r#"
+title = "Game"
+speed = inf
+
+[[characters]]
+name = "Mario"
+age = 35
+
+[[characters]]
+name = "Luigi"
+height = 1.8
+
+[settings.audio]
+volume = 0.5
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        converted.value,
+        dent(
+            "{ title: Game speed: inf characters: [ { name: Mario age: 35 } \
+             { name: Luigi height: 1.8 } ] settings: { audio: { volume: 0.5 } } }"
+        )
+    );
+    assert!(converted.warnings.is_empty());
+}
+
+#[test]
+fn datetimes() {
+    let converted =
+        toml::from_str("[release]\ndates = [1985-09-13, 2023-10-20T10:00:00Z]\n").unwrap();
+
+    assert_eq!(
+        converted.value,
+        dent("{ release: { dates: [ \"1985-09-13\" \"2023-10-20T10:00:00Z\" ] } }")
+    );
+    let warnings: Vec<_> = converted.warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        warnings,
+        vec![
+            "release.dates[0]: datetime 1985-09-13 was converted to a string",
+            "release.dates[1]: datetime 2023-10-20T10:00:00Z was converted to a string",
+        ]
+    );
+}
+
+#[test]
+fn invalid_toml() {
+    match toml::from_str("a = 1\nb = [1, }\n") {
+        Err(Error::InvalidSource {
+            format, location, ..
+        }) => {
+            assert_eq!(format, "TOML");
+            assert_eq!(location.span.line, 2);
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn to_toml() {
+    let converted = toml::to_string(&dent(
+        "{ title: Game rival: none characters: [ { name: Mario age: 35 } ] }",
+    ))
+    .unwrap();
+
+    assert_eq!(
+        converted.value,
+        "title = \"Game\"\n\n[[characters]]\nname = \"Mario\"\nage = 35\n"
+    );
+    assert_eq!(converted.warnings.len(), 1);
+    assert_eq!(
+        converted.warnings[0].to_string(),
+        "rival: the entry was left out, as TOML has no null"
+    );
+}
+
+#[test]
+fn unrepresentable() {
+    assert_eq!(
+        toml::to_string(&dent("[ 1 2 ]")),
+        Err(Error::Conversion {
+            path: String::new(),
+            message: "only dictionaries can be written as TOML documents".to_string(),
+        })
+    );
+    assert_eq!(
+        toml::to_string(&dent("{ a: { b: [ 1 none ] } }")),
+        Err(Error::Conversion {
+            path: "a.b[1]".to_string(),
+            message: "TOML has no null to write `none` as".to_string(),
+        })
+    );
+}
+
+#[test]
+fn round_trip() {
+    let value = dent("{ a: 1 b: [ 1.5 \"2\" true ] c: { d: { e: \"none\" } } }");
+    let converted = toml::from_str(&toml::to_string(&value).unwrap().value).unwrap();
+
+    assert_eq!(converted.value, value);
+}
@@ -0,0 +1,176 @@
+use super::*;
+use crate::yaml;
+
+fn dent(source: &str) -> OwnedValue {
+    Dent::default().parse(source).unwrap().into_owned()
+}
+
+#[test]
+fn from_yaml() {
+    let converted = yaml::from_str(
+        "name: Mario\nage: 35\nheight: 155.0\nrival: ~\nnickname:\nalive: true\n\
+         title: \"true\"\nskills: [jumps, grows]\nspeed: -.inf\ncode: '042'\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        converted.value,
+        dent(
+            "{ name: Mario age: 35 height: 155.0 rival: none nickname: none alive: true \
+             title: \"true\" skills: [ jumps grows ] speed: -inf code: \"042\" }"
+        )
+    );
+    assert!(converted.warnings.is_empty());
+}
+
+#[test]
+fn tags() {
+    let converted =
+        yaml::from_str("a: !!str 42\nb: !!float 1\nc: !!int 7\nd: !custom hello\n").unwrap();
+
+    assert_eq!(converted.value, dent("{ a: \"42\" b: 1.0 c: 7 d: hello }"));
+    assert_eq!(converted.warnings.len(), 1);
+    assert_eq!(
+        converted.warnings[0].to_string(),
+        "d: the tag `!custom` is not supported and was ignored"
+    );
+    assert_eq!(
+        converted.warnings[0].location.as_ref().unwrap().span.line,
+        4
+    );
+
+    let converted = yaml::from_str("a: !!seq [1]\nb: !set { x: ~ }\n").unwrap();
+    assert_eq!(converted.value, dent("{ a: [ 1 ] b: { x: none } }"));
+    let warnings: Vec<_> = converted.warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        warnings,
+        vec!["b: the tag `!set` is not supported and was ignored"]
+    );
+
+    match yaml::from_str("a: !!int seven\n") {
+        Err(Error::InvalidSource { location, .. }) => assert_eq!(location.span.start, 9),
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn aliases() {
+    let converted = yaml::from_str(
+        "base: &base { lives: 3 }\ncharacters:\n  - *base\n  - lives: 5\nname: &name Mario\nplayer: *name\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        converted.value,
+        dent(
+            "{ base: { lives: 3 } characters: [ { lives: 3 } { lives: 5 } ] \
+             name: Mario player: Mario }"
+        )
+    );
+    let paths: Vec<_> = converted.warnings.iter().map(|w| w.path.as_str()).collect();
+    assert_eq!(paths, vec!["characters[0]", "player"]);
+    assert_eq!(
+        converted.warnings[0].location.as_ref().unwrap().span,
+        Span::new(41, 42, 3, 5)
+    );
+}
+
+#[test]
+fn merge_keys() {
+    let converted = yaml::from_str("base: &base { lives: 3 }\nmario:\n  <<: *base\n").unwrap();
+
+    assert_eq!(
+        converted.value,
+        dent("{ base: { lives: 3 } mario: { \"<<\": { lives: 3 } } }")
+    );
+    let warnings: Vec<_> = converted.warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        warnings,
+        vec![
+            "mario.<<: merge keys are not supported, so the entry was kept with the key `<<`",
+            "mario.<<: the alias was replaced by a copy of its anchor's value",
+        ]
+    );
+    assert_eq!(
+        converted.warnings[0].location.as_ref().unwrap().span.line,
+        3
+    );
+}
+
+#[test]
+fn keys() {
+    let converted = yaml::from_str("1: one\ntrue: yes\nname: a\nname: b\n").unwrap();
+
+    assert_eq!(
+        converted.value,
+        dent("{ \"1\": one \"true\": yes name: b }")
+    );
+    let warnings: Vec<_> = converted.warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        warnings,
+        vec![
+            "1: the key was converted to a string",
+            "true: the key was converted to a string",
+            "name: the key is duplicated, and only its last value was kept",
+        ]
+    );
+
+    assert_eq!(
+        yaml::from_str("? [a, b]\n: 1\n"),
+        Err(Error::Conversion {
+            path: String::new(),
+            message: "dictionary keys cannot be sequences or mappings".to_string(),
+        })
+    );
+}
+
+#[test]
+fn documents() {
+    assert_eq!(yaml::from_str("").unwrap().value, Value::None);
+    assert_eq!(yaml::from_str("--- 5\n").unwrap().value, Value::Int(5));
+    assert!(matches!(
+        yaml::from_str("a: 1\n---\nb: 2\n"),
+        Err(Error::Conversion { .. })
+    ));
+}
+
+#[test]
+fn invalid_yaml() {
+    match yaml::from_str("a: [1, 2\nb: 3\n") {
+        Err(Error::InvalidSource {
+            format, location, ..
+        }) => {
+            assert_eq!(format, "YAML");
+            assert_eq!(location.span.line, 2);
+        }
+        other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn to_yaml() {
+    let value = dent(
+        "{ name: Mario title: \"true\" rival: none speed: inf height: 155.0 \
+         skills: [ jumps grows ] empty: {} }",
+    );
+
+    assert_eq!(
+        yaml::to_string(&value),
+        "name: Mario\ntitle: \"true\"\nrival: ~\nspeed: .inf\nheight: 155.0\n\
+         skills:\n  - jumps\n  - grows\nempty: {}\n"
+    );
+    assert_eq!(yaml::to_string(&Value::Int(1)), "1\n");
+}
+
+#[test]
+fn round_trip() {
+    let value = dent(
+        "{ a: [ 1 -2.5 \"3\" none \"none\" \"~\" \"\" ] b: { \"key with: colon\": nan } c: \"0x10\" }",
+    );
+    let converted = yaml::from_str(&yaml::to_string(&value)).unwrap();
+
+    assert!(converted.warnings.is_empty());
+    assert_eq!(converted.value["a"], value["a"]);
+    assert_eq!(converted.value["c"], value["c"]);
+    assert!(matches!(converted.value["b"]["key with: colon"], Value::Float(f) if f.is_nan()));
+}
@@ -1,10 +1,15 @@
-use crate::{Error, Result};
+use std::borrow::Cow;
+
+use crate::{Error, Location, Result, Span};
 
 pub(crate) struct Tokenizer<'s> {
     input: &'s str,
     chars: std::str::Chars<'s>,
     char: Option<char>,
     pos: usize,
+    line: usize,
+    column: usize,
+    span: Span,
 }
 
 #[derive(Debug, PartialEq)]
@@ -14,9 +19,10 @@ pub(crate) enum Token<'s> {
     OpenBrace,
     CloseBrace,
     Colon,
-    String(&'s str),
+    String(Cow<'s, str>),
     Number(&'s str),
     Bool(bool),
+    None,
     At,
     Comment,
     Eof,
@@ -33,12 +39,48 @@ impl<'s> Token<'s> {
             Token::String(_) => "STRING",
             Token::Number(_) => "NUMBER",
             Token::Bool(_) => "BOOL",
+            Token::None => "NONE",
             Token::Comment => "COMMENT",
             Token::At => "AT",
             Token::Eof => "EOF",
         }
         .to_string()
     }
+
+    /// Returns the key the token stands for in a dictionary. `none`, `inf`
+    /// and `nan` are only keywords as values, so they are also keys like any
+    /// other word.
+    pub fn key(&self) -> Option<Cow<'s, str>> {
+        match self {
+            Token::String(s) => Some(s.clone()),
+            Token::None => Some(Cow::Borrowed("none")),
+            Token::Number(s) if NUMBER_WORDS.contains(s) => Some(Cow::Borrowed(s)),
+            _ => None,
+        }
+    }
+}
+
+/// The words that are numbers rather than strings.
+const NUMBER_WORDS: &[&str] = &["inf", "+inf", "-inf", "nan", "+nan", "-nan"];
+
+/// Returns a human-readable description of a token type name, as returned by
+/// `Token::type_name`.
+pub(crate) fn describe_token(type_name: &str) -> &str {
+    match type_name {
+        "BRACKET_OPEN" => "`[`",
+        "BRACKET_CLOSE" => "`]`",
+        "BRACE_OPEN" => "`{`",
+        "BRACE_CLOSE" => "`}`",
+        "COLON" => "`:`",
+        "STRING" => "a string",
+        "NUMBER" => "a number",
+        "BOOL" => "a boolean",
+        "NONE" => "`none`",
+        "COMMENT" => "a comment",
+        "AT" => "`@`",
+        "EOF" => "end of file",
+        other => other,
+    }
 }
 
 impl<'s> Tokenizer<'s> {
@@ -50,12 +92,22 @@ impl<'s> Tokenizer<'s> {
             chars,
             char,
             pos: 0,
+            line: 1,
+            column: 1,
+            span: Span::new(0, 0, 1, 1),
         }
     }
 
+    /// Returns the span of the token last returned by `next`.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
     pub fn next(&mut self) -> Result<Token<'s>> {
         self.skip_whitespace();
 
+        let (start, line, column) = (self.pos, self.line, self.column);
+
         let r = match self.char {
             None => Ok(Token::Eof),
             Some(c) => match c {
@@ -94,33 +146,14 @@ impl<'s> Tokenizer<'s> {
                     Ok(Token::Comment)
                 }
                 '"' => {
-                    self.next_char();
-                    let start = self.pos;
-                    while let Some(c) = self.char {
-                        if c == '"' {
-                            break;
-                        }
-                        self.next_char();
+                    if self.input[self.pos..].starts_with("\"\"\"") {
+                        self.raw_string(start, line, column)
+                    } else {
+                        self.string(start, line, column)
                     }
-                    let end = self.pos;
-                    self.next_char();
-
-                    let s = &self.input[start..end];
-
-                    Ok(Token::String(s))
-                }
-                '0'..='9' => {
-                    let start = self.pos;
-                    while let Some(c) = self.char {
-                        if !c.is_ascii_digit() && c != '.' {
-                            break;
-                        }
-                        self.next_char();
-                    }
-                    let end = self.pos;
-                    let s = &self.input[start..end];
-                    Ok(Token::Number(s))
                 }
+                '0'..='9' => Ok(self.number()),
+                '-' | '+' if self.peek().is_some_and(|c| c.is_ascii_digit()) => Ok(self.number()),
                 c if c.is_alphabetic()
                     || c == '_'
                     || c == '-'
@@ -131,6 +164,7 @@ impl<'s> Tokenizer<'s> {
                     || c == '\\' =>
                 {
                     let start = self.pos;
+                    self.next_char();
                     while let Some(c) = self.char {
                         if !c.is_alphanumeric() && c != '_' {
                             break;
@@ -141,18 +175,191 @@ impl<'s> Tokenizer<'s> {
                     let s = &self.input[start..end];
 
                     if s == "true" {
-                        return Ok(Token::Bool(true));
+                        Ok(Token::Bool(true))
                     } else if s == "false" {
-                        return Ok(Token::Bool(false));
+                        Ok(Token::Bool(false))
+                    } else if s == "none" {
+                        Ok(Token::None)
+                    } else if NUMBER_WORDS.contains(&s) {
+                        Ok(Token::Number(s))
+                    } else {
+                        Ok(Token::String(Cow::Borrowed(s)))
                     }
-                    Ok(Token::String(s))
                 }
-                _ => Err(Error::UnexpectedChar(c)),
+                _ => Err(Error::UnexpectedChar {
+                    char: c,
+                    location: Location::new(Span::new(start, start + c.len_utf8(), line, column)),
+                }),
             },
         };
+        self.span = Span::new(start, self.pos, line, column);
         r
     }
 
+    /// Reads a numeric literal.
+    ///
+    /// This only finds the extent of the literal: a sign, then letters, digits,
+    /// `_` and `.`, and signs directly following a decimal exponent. Whether it
+    /// is a valid number is checked when parsing it.
+    fn number(&mut self) -> Token<'s> {
+        let start = self.pos;
+        self.next_char();
+        let is_hex = self.input[start..]
+            .trim_start_matches(['-', '+'])
+            .starts_with("0x");
+
+        let mut prev = None;
+        while let Some(c) = self.char {
+            let exponent_sign =
+                (c == '-' || c == '+') && matches!(prev, Some('e' | 'E')) && !is_hex;
+            if !c.is_alphanumeric() && c != '_' && c != '.' && !exponent_sign {
+                break;
+            }
+            prev = Some(c);
+            self.next_char();
+        }
+
+        Token::Number(&self.input[start..self.pos])
+    }
+
+    /// Reads a quoted string, processing escape sequences.
+    ///
+    /// The string is borrowed from the input unless it contains escapes.
+    fn string(&mut self, start: usize, line: usize, column: usize) -> Result<Token<'s>> {
+        self.next_char();
+        let content_start = self.pos;
+        let mut owned: Option<String> = None;
+
+        loop {
+            match self.char {
+                None => {
+                    return Err(Error::UnterminatedString {
+                        location: Location::new(Span::new(start, start + 1, line, column)),
+                    })
+                }
+                Some('"') => break,
+                Some('\\') => {
+                    let owned = owned
+                        .get_or_insert_with(|| self.input[content_start..self.pos].to_string());
+                    let (escape_start, escape_line, escape_column) =
+                        (self.pos, self.line, self.column);
+                    self.next_char();
+
+                    if self.char.is_none() {
+                        continue;
+                    }
+
+                    let c = match self.char {
+                        Some('"') => Some('"'),
+                        Some('\\') => Some('\\'),
+                        Some('n') => Some('\n'),
+                        Some('t') => Some('\t'),
+                        Some('r') => Some('\r'),
+                        Some('0') => Some('\0'),
+                        Some('u') => self.unicode_escape(),
+                        _ => None,
+                    };
+                    if self.char.is_some() {
+                        self.next_char();
+                    }
+                    match c {
+                        Some(c) => owned.push(c),
+                        None => {
+                            return Err(Error::InvalidEscape {
+                                sequence: self.input[escape_start..self.pos].to_string(),
+                                location: Location::new(Span::new(
+                                    escape_start,
+                                    self.pos,
+                                    escape_line,
+                                    escape_column,
+                                )),
+                            })
+                        }
+                    }
+                }
+                Some(c) => {
+                    if let Some(owned) = owned.as_mut() {
+                        owned.push(c);
+                    }
+                    self.next_char();
+                }
+            }
+        }
+
+        let content_end = self.pos;
+        self.next_char();
+
+        Ok(Token::String(match owned {
+            Some(s) => Cow::Owned(s),
+            None => Cow::Borrowed(&self.input[content_start..content_end]),
+        }))
+    }
+
+    /// Reads the `{...}` part of a `\u{...}` escape, leaving the tokenizer on
+    /// the closing brace.
+    fn unicode_escape(&mut self) -> Option<char> {
+        self.next_char();
+        if self.char != Some('{') {
+            return None;
+        }
+        self.next_char();
+
+        let digits_start = self.pos;
+        while let Some(c) = self.char {
+            if !c.is_ascii_hexdigit() {
+                break;
+            }
+            self.next_char();
+        }
+        let digits = &self.input[digits_start..self.pos];
+
+        if self.char != Some('}') || digits.is_empty() || digits.len() > 6 {
+            return None;
+        }
+
+        u32::from_str_radix(digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+    }
+
+    /// Reads a triple-quoted string. Its contents are taken verbatim, without
+    /// processing escapes, except that a line break directly after the opening
+    /// quotes is dropped.
+    fn raw_string(&mut self, start: usize, line: usize, column: usize) -> Result<Token<'s>> {
+        for _ in 0..3 {
+            self.next_char();
+        }
+        if self.char == Some('\r') && self.input[self.pos..].starts_with("\r\n") {
+            self.next_char();
+        }
+        if self.char == Some('\n') {
+            self.next_char();
+        }
+
+        let content_start = self.pos;
+        while !self.input[self.pos..].starts_with("\"\"\"") {
+            if self.char.is_none() {
+                return Err(Error::UnterminatedString {
+                    location: Location::new(Span::new(start, start + 3, line, column)),
+                });
+            }
+            self.next_char();
+        }
+        let content_end = self.pos;
+
+        for _ in 0..3 {
+            self.next_char();
+        }
+
+        Ok(Token::String(Cow::Borrowed(
+            &self.input[content_start..content_end],
+        )))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.clone().next()
+    }
+
     fn skip_whitespace(&mut self) {
         while let Some(c) = self.char {
             if !c.is_whitespace() {
@@ -163,6 +370,14 @@ impl<'s> Tokenizer<'s> {
     }
 
     fn next_char(&mut self) {
+        match self.char {
+            Some('\n') => {
+                self.line += 1;
+                self.column = 1;
+            }
+            Some(_) => self.column += 1,
+            None => {}
+        }
         self.pos += self.char.map(|c| c.len_utf8()).unwrap_or(0);
         self.char = self.chars.next();
     }
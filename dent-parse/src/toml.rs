@@ -0,0 +1,163 @@
+//! Converting between TOML and `Value`s.
+//!
+//! TOML tables become dictionaries with their keys in the same order, and
+//! the other way around. Integers, floats (including `inf` and `nan`),
+//! booleans, strings and arrays map to the matching `Value`s.
+//!
+//! Not everything has a counterpart in the other format:
+//! - Dent has no datetimes, so TOML datetimes become strings in RFC 3339
+//!   format, with a warning.
+//! - TOML has no null, so dictionary entries with the value `none` are left
+//!   out, with a warning. A `none` anywhere else cannot be left out without
+//!   changing the meaning of the document, and fails the conversion.
+//! - A TOML document is always a table, so only dictionaries can be written
+//!   as TOML.
+//!
+//! # Examples
+//! ```
+//! use dent_parse::{toml, Emitter};
+//!
+//! let converted = toml::from_str("name = \"Mario\"\nborn = 1981-07-09\n").unwrap();
+//! assert_eq!(
+//!     Emitter::compact().emit(&converted.value),
+//!     "{ name: Mario born: \"1981-07-09\" }"
+//! );
+//! assert_eq!(
+//!     converted.warnings[0].to_string(),
+//!     "born: datetime 1981-07-09 was converted to a string"
+//! );
+//! ```
+
+use crate::{
+    key_path::KeyPath, ConversionWarning, Converted, Error, Location, OwnedValue, Result, Span,
+    Value,
+};
+
+/// Parses a TOML document into a `Value`, which is always a dictionary.
+///
+/// Fails with `Error::InvalidSource`, located in `toml`, if it is not valid
+/// TOML.
+pub fn from_str(toml: &str) -> Result<Converted<OwnedValue>> {
+    let table: ::toml::Table = toml.parse().map_err(|e: ::toml::de::Error| {
+        let span = e.span().unwrap_or(0..0);
+        Error::InvalidSource {
+            format: "TOML".to_string(),
+            message: e.message().to_string(),
+            location: Location::new(Span::at_range(toml, span.start, span.end)),
+        }
+    })?;
+    Ok(from_toml(&::toml::Value::Table(table)))
+}
+
+/// Converts a parsed TOML value into a `Value`.
+pub fn from_toml(value: &::toml::Value) -> Converted<OwnedValue> {
+    let mut warnings = Vec::new();
+    let value = from_toml_at(value, KeyPath::Root, &mut warnings);
+    Converted { value, warnings }
+}
+
+fn from_toml_at(
+    value: &::toml::Value,
+    path: KeyPath,
+    warnings: &mut Vec<ConversionWarning>,
+) -> OwnedValue {
+    match value {
+        ::toml::Value::String(s) => Value::from(s.clone()),
+        ::toml::Value::Integer(i) => Value::Int(*i),
+        ::toml::Value::Float(f) => Value::Float(*f),
+        ::toml::Value::Boolean(b) => Value::Bool(*b),
+        ::toml::Value::Datetime(d) => {
+            warnings.push(ConversionWarning {
+                path: path.to_string(),
+                message: format!("datetime {} was converted to a string", d),
+                location: None,
+            });
+            Value::from(d.to_string())
+        }
+        ::toml::Value::Array(a) => Value::List(
+            a.iter()
+                .enumerate()
+                .map(|(i, v)| from_toml_at(v, KeyPath::Index(&path, i), warnings))
+                .collect(),
+        ),
+        ::toml::Value::Table(t) => Value::Dict(
+            t.iter()
+                .map(|(k, v)| {
+                    let value = from_toml_at(v, KeyPath::Key(&path, k), warnings);
+                    (k.clone().into(), value)
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// Converts a dictionary into a TOML value.
+///
+/// Fails with `Error::Conversion` if the value is not a dictionary, or has a
+/// `none` that is not the value of a dictionary entry.
+pub fn to_toml(value: &Value) -> Result<Converted<::toml::Value>> {
+    if !value.is_dict() {
+        return Err(Error::Conversion {
+            path: String::new(),
+            message: "only dictionaries can be written as TOML documents".to_string(),
+        });
+    }
+
+    let mut warnings = Vec::new();
+    let value = to_toml_at(value, KeyPath::Root, &mut warnings)?;
+    Ok(Converted { value, warnings })
+}
+
+fn to_toml_at(
+    value: &Value,
+    path: KeyPath,
+    warnings: &mut Vec<ConversionWarning>,
+) -> Result<::toml::Value> {
+    Ok(match value {
+        Value::None => {
+            return Err(Error::Conversion {
+                path: path.to_string(),
+                message: "TOML has no null to write `none` as".to_string(),
+            })
+        }
+        Value::Str(s) => ::toml::Value::String(s.to_string()),
+        Value::Int(i) => ::toml::Value::Integer(*i),
+        Value::Float(f) => ::toml::Value::Float(*f),
+        Value::Bool(b) => ::toml::Value::Boolean(*b),
+        Value::List(l) => ::toml::Value::Array(
+            l.iter()
+                .enumerate()
+                .map(|(i, v)| to_toml_at(v, KeyPath::Index(&path, i), warnings))
+                .collect::<Result<_>>()?,
+        ),
+        Value::Dict(d) => {
+            let mut table = ::toml::Table::new();
+            for (k, v) in d {
+                let path = KeyPath::Key(&path, k);
+                if v.is_none() {
+                    warnings.push(ConversionWarning {
+                        path: path.to_string(),
+                        message: "the entry was left out, as TOML has no null".to_string(),
+                        location: None,
+                    });
+                    continue;
+                }
+                table.insert(k.to_string(), to_toml_at(v, path, warnings)?);
+            }
+            ::toml::Value::Table(table)
+        }
+    })
+}
+
+/// Writes a dictionary as a TOML document.
+///
+/// Fails with `Error::Conversion` if the value is not a dictionary, or has a
+/// `none` that is not the value of a dictionary entry.
+pub fn to_string(value: &Value) -> Result<Converted<String>> {
+    let Converted { value, warnings } = to_toml(value)?;
+    let value = ::toml::to_string(&value).map_err(|e| Error::Conversion {
+        path: String::new(),
+        message: e.to_string(),
+    })?;
+    Ok(Converted { value, warnings })
+}
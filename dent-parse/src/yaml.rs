@@ -0,0 +1,418 @@
+//! Converting between YAML and `Value`s.
+//!
+//! Plain scalars are resolved with the YAML 1.2 core schema, as they would be
+//! by most YAML libraries: `~`, `null` and empty values become `Value::None`,
+//! `true` and `false` booleans, and `.inf` and `.nan` floats. Quoted scalars
+//! are always strings. Mappings become dictionaries with their keys in the
+//! same order.
+//!
+//! Not everything has a counterpart in the other format:
+//! - Dent has no references, so an alias (`*name`) is replaced by a copy of
+//!   the value of its anchor, with a warning.
+//! - Tags other than the standard `!!str`, `!!int`, `!!float`, `!!bool` and
+//!   `!!null` on scalars, and `!!seq` and `!!map` on sequences and mappings,
+//!   are ignored, with a warning.
+//! - Merge keys (`<<: *base`) are not supported, so they are kept as entries
+//!   with the key `<<`, with a warning.
+//! - Dictionary keys are always strings, so keys that are numbers, booleans
+//!   or null are converted to strings, with a warning. Keys that are
+//!   sequences or mappings fail the conversion.
+//! - A stream with several documents fails the conversion.
+//!
+//! Every `Value` can be written as YAML.
+//!
+//! # Examples
+//! ```
+//! use dent_parse::{yaml, Emitter};
+//!
+//! let converted = yaml::from_str("base: &base { lives: 3 }\nmario: *base\n").unwrap();
+//! assert_eq!(
+//!     Emitter::compact().emit(&converted.value),
+//!     "{ base: { lives: 3 } mario: { lives: 3 } }"
+//! );
+//! assert_eq!(
+//!     converted.warnings[0].to_string(),
+//!     "mario: the alias was replaced by a copy of its anchor's value"
+//! );
+//! ```
+
+use std::{collections::HashMap, fmt::Write};
+
+use yaml_rust2::{
+    parser::{Event, MarkedEventReceiver, Parser, Tag},
+    scanner::{Marker, TScalarStyle},
+    yaml::Hash,
+    Yaml, YamlEmitter,
+};
+
+use crate::{
+    span::write_key, ConversionWarning, Converted, Dict, Emitter, Error, Location, OwnedValue,
+    Result, Span, Value,
+};
+
+/// Parses a YAML document into a `Value`. An empty stream has the value
+/// `none`.
+///
+/// Fails with `Error::InvalidSource`, located in `yaml`, if it is not valid
+/// YAML or has a scalar that does not match its tag, and with
+/// `Error::Conversion` if it cannot be represented as a `Value`.
+pub fn from_str(yaml: &str) -> Result<Converted<OwnedValue>> {
+    let mut builder = Builder {
+        source: yaml,
+        stack: Vec::new(),
+        anchors: HashMap::new(),
+        documents: Vec::new(),
+        warnings: Vec::new(),
+        error: None,
+    };
+
+    Parser::new_from_str(yaml)
+        .load(&mut builder, true)
+        .map_err(|e| invalid(yaml, e.info().to_string(), e.marker()))?;
+
+    if let Some(error) = builder.error {
+        return Err(error);
+    }
+
+    let value = match builder.documents.len() {
+        0 => Value::None,
+        1 => builder.documents.remove(0),
+        n => {
+            return Err(Error::Conversion {
+                path: String::new(),
+                message: format!(
+                    "the YAML stream has {} documents, but only one can be converted",
+                    n
+                ),
+            })
+        }
+    };
+
+    Ok(Converted {
+        value,
+        warnings: builder.warnings,
+    })
+}
+
+fn invalid(source: &str, message: String, marker: &Marker) -> Error {
+    Error::InvalidSource {
+        format: "YAML".to_string(),
+        message,
+        location: location(source, marker),
+    }
+}
+
+/// The prefix the `!!` handle stands for.
+const CORE_TAG_PREFIX: &str = "tag:yaml.org,2002:";
+
+/// Returns a tag as it is written, with `!!` for the standard ones.
+fn tag_name(tag: &Tag) -> String {
+    match tag.handle.as_str() {
+        CORE_TAG_PREFIX => format!("!!{}", tag.suffix),
+        handle => format!("{}{}", handle, tag.suffix),
+    }
+}
+
+/// Returns the location of the character a marker points at.
+fn location(source: &str, marker: &Marker) -> Location {
+    let (start, len) = source
+        .char_indices()
+        .nth(marker.index())
+        .map_or((source.len(), 0), |(i, c)| (i, c.len_utf8()));
+    Location::new(Span::at_range(source, start, start + len))
+}
+
+/// Builds a `Value` from the events of the YAML parser.
+struct Builder<'a> {
+    source: &'a str,
+    /// The sequences and mappings that have been started but not ended
+    stack: Vec<Frame>,
+    anchors: HashMap<usize, OwnedValue>,
+    documents: Vec<OwnedValue>,
+    warnings: Vec<ConversionWarning>,
+    /// The first error, after which events are ignored
+    error: Option<Error>,
+}
+
+enum Frame {
+    List {
+        items: Vec<OwnedValue>,
+        anchor: usize,
+    },
+    Dict {
+        dict: Dict<'static>,
+        /// The key of the entry whose value comes next, if it has been read
+        key: Option<String>,
+        anchor: usize,
+    },
+}
+
+impl MarkedEventReceiver for Builder<'_> {
+    fn on_event(&mut self, event: Event, marker: Marker) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Err(e) = self.event(event, marker) {
+            self.error = Some(e);
+        }
+    }
+}
+
+impl Builder<'_> {
+    fn event(&mut self, event: Event, marker: Marker) -> Result<()> {
+        match event {
+            Event::Scalar(text, style, anchor, tag) => {
+                let merge = text == "<<" && style == TScalarStyle::Plain && tag.is_none();
+                let value = self.scalar(&text, style, tag, &marker)?;
+                if self.expects_key() {
+                    if anchor > 0 {
+                        self.anchors.insert(anchor, value.clone());
+                    }
+                    self.key(text, value, &marker)?;
+                    if merge {
+                        self.warn(
+                            "merge keys are not supported, so the entry was kept with the key `<<`",
+                            &marker,
+                        );
+                    }
+                } else {
+                    self.insert(value, anchor);
+                }
+            }
+            Event::Alias(id) => {
+                let value = self.anchors.get(&id).cloned().unwrap_or(Value::None);
+                if self.expects_key() {
+                    let text = match &value {
+                        Value::Str(s) => s.to_string(),
+                        v => Emitter::compact().emit(v),
+                    };
+                    self.key(text, value, &marker)?;
+                } else {
+                    self.warn(
+                        "the alias was replaced by a copy of its anchor's value",
+                        &marker,
+                    );
+                    self.insert(value, 0);
+                }
+            }
+            Event::SequenceStart(anchor, tag) => {
+                self.check_not_key()?;
+                self.check_collection_tag(tag, "seq", &marker);
+                self.stack.push(Frame::List {
+                    items: Vec::new(),
+                    anchor,
+                });
+            }
+            Event::MappingStart(anchor, tag) => {
+                self.check_not_key()?;
+                self.check_collection_tag(tag, "map", &marker);
+                self.stack.push(Frame::Dict {
+                    dict: Dict::new(),
+                    key: None,
+                    anchor,
+                });
+            }
+            Event::SequenceEnd | Event::MappingEnd => match self.stack.pop() {
+                Some(Frame::List { items, anchor }) => self.insert(Value::List(items), anchor),
+                Some(Frame::Dict { dict, anchor, .. }) => self.insert(Value::Dict(dict), anchor),
+                None => {}
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Resolves a scalar to a `Value`, following its tag if it has one.
+    fn scalar(
+        &mut self,
+        text: &str,
+        style: TScalarStyle,
+        tag: Option<Tag>,
+        marker: &Marker,
+    ) -> Result<OwnedValue> {
+        let plain = style == TScalarStyle::Plain;
+
+        let tag = match tag {
+            Some(tag) => tag,
+            None if plain => return Ok(resolve(text)),
+            None => return Ok(Value::from(text.to_string())),
+        };
+
+        let resolved = match (tag.handle.as_str(), tag.suffix.as_str()) {
+            (CORE_TAG_PREFIX, "str") => Some(Value::from(text.to_string())),
+            (CORE_TAG_PREFIX, "bool") => text.parse().ok().map(Value::Bool),
+            (CORE_TAG_PREFIX, "int") => Some(resolve(text)).filter(Value::is_int),
+            (CORE_TAG_PREFIX, "float") => match resolve(text) {
+                Value::Int(i) => Some(Value::Float(i as f64)),
+                v @ Value::Float(_) => Some(v),
+                _ => None,
+            },
+            (CORE_TAG_PREFIX, "null") => Some(resolve(text)).filter(Value::is_none),
+            _ => {
+                let message = format!(
+                    "the tag `{}` is not supported and was ignored",
+                    tag_name(&tag)
+                );
+                self.warn(&message, marker);
+                return Ok(if plain {
+                    resolve(text)
+                } else {
+                    Value::from(text.to_string())
+                });
+            }
+        };
+
+        resolved.ok_or_else(|| {
+            let message = format!(
+                "`{}` is not a valid value for the tag `{}`",
+                text,
+                tag_name(&tag)
+            );
+            invalid(self.source, message, marker)
+        })
+    }
+
+    /// Warns about a tag on a sequence or mapping, unless it is the standard
+    /// one for it.
+    fn check_collection_tag(&mut self, tag: Option<Tag>, standard: &str, marker: &Marker) {
+        if let Some(tag) = tag {
+            if tag.handle != CORE_TAG_PREFIX || tag.suffix != standard {
+                let message = format!(
+                    "the tag `{}` is not supported and was ignored",
+                    tag_name(&tag)
+                );
+                self.warn(&message, marker);
+            }
+        }
+    }
+
+    fn expects_key(&self) -> bool {
+        matches!(self.stack.last(), Some(Frame::Dict { key: None, .. }))
+    }
+
+    fn check_not_key(&self) -> Result<()> {
+        if self.expects_key() {
+            return Err(Error::Conversion {
+                path: self.path(),
+                message: "dictionary keys cannot be sequences or mappings".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Sets the key of the next entry of the current mapping.
+    fn key(&mut self, text: String, value: OwnedValue, marker: &Marker) -> Result<()> {
+        if value.is_list() || value.is_dict() {
+            return self.check_not_key();
+        }
+        if let Some(Frame::Dict { key, .. }) = self.stack.last_mut() {
+            *key = Some(text);
+        }
+        if !value.is_str() {
+            self.warn("the key was converted to a string", marker);
+        }
+        Ok(())
+    }
+
+    /// Adds a finished value to the current sequence or mapping, or as a
+    /// document if there is none.
+    fn insert(&mut self, value: OwnedValue, anchor: usize) {
+        if anchor > 0 {
+            self.anchors.insert(anchor, value.clone());
+        }
+
+        let path = self.path();
+        match self.stack.last_mut() {
+            None => self.documents.push(value),
+            Some(Frame::List { items, .. }) => items.push(value),
+            Some(Frame::Dict { dict, key, .. }) => {
+                let key = key.take().unwrap_or_default();
+                if dict.insert(key.into(), value).is_some() {
+                    self.warnings.push(ConversionWarning {
+                        path,
+                        message: "the key is duplicated, and only its last value was kept"
+                            .to_string(),
+                        location: None,
+                    });
+                }
+            }
+        }
+    }
+
+    fn warn(&mut self, message: &str, marker: &Marker) {
+        self.warnings.push(ConversionWarning {
+            path: self.path(),
+            message: message.to_string(),
+            location: Some(location(self.source, marker)),
+        });
+    }
+
+    /// Returns the key path of the next value.
+    fn path(&self) -> String {
+        let mut path = String::new();
+        for frame in &self.stack {
+            match frame {
+                Frame::List { items, .. } => {
+                    let _ = write!(path, "[{}]", items.len());
+                }
+                Frame::Dict { key: Some(key), .. } => {
+                    let first = path.is_empty();
+                    let _ = write_key(&mut path, first, key);
+                }
+                Frame::Dict { key: None, .. } => {}
+            }
+        }
+        path
+    }
+}
+
+/// Resolves a plain scalar with the core schema.
+fn resolve(text: &str) -> OwnedValue {
+    if text.is_empty() {
+        return Value::None;
+    }
+    match Yaml::from_str(text) {
+        Yaml::Null => Value::None,
+        Yaml::Boolean(b) => Value::Bool(b),
+        Yaml::Integer(i) => Value::Int(i),
+        real @ Yaml::Real(_) => real.as_f64().map_or(Value::None, Value::Float),
+        _ => Value::from(text.to_string()),
+    }
+}
+
+/// Converts a `Value` into a YAML value.
+pub fn to_yaml(value: &Value) -> Yaml {
+    match value {
+        Value::None => Yaml::Null,
+        Value::Str(s) => Yaml::String(s.to_string()),
+        Value::Int(i) => Yaml::Integer(*i),
+        Value::Float(f) if f.is_nan() => Yaml::Real(".nan".to_string()),
+        Value::Float(f) if f.is_infinite() => {
+            Yaml::Real(if *f > 0.0 { ".inf" } else { "-.inf" }.to_string())
+        }
+        // `Debug` always includes a `.` or an exponent
+        Value::Float(f) => Yaml::Real(format!("{:?}", f)),
+        Value::Bool(b) => Yaml::Boolean(*b),
+        Value::List(l) => Yaml::Array(l.iter().map(to_yaml).collect()),
+        Value::Dict(d) => Yaml::Hash(
+            d.iter()
+                .map(|(k, v)| (Yaml::String(k.to_string()), to_yaml(v)))
+                .collect::<Hash>(),
+        ),
+    }
+}
+
+/// Writes a `Value` as a YAML document.
+pub fn to_string(value: &Value) -> String {
+    let mut out = String::new();
+    YamlEmitter::new(&mut out)
+        .dump(&to_yaml(value))
+        .expect("keys are always strings");
+
+    // The emitter starts every document with a `---` marker
+    let out = out
+        .strip_prefix("---\n")
+        .or_else(|| out.strip_prefix("--- "))
+        .unwrap_or(&out);
+    format!("{}\n", out)
+}
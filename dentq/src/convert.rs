@@ -0,0 +1,110 @@
+use std::{io::Read, path::PathBuf};
+
+use clap::{Args, ValueEnum};
+use dent_parse::{diagnostics::Diagnostic, json, toml, yaml, Converted, Dent, Emitter, OwnedValue};
+
+use crate::fail;
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Dent,
+    Json,
+    Yaml,
+    Toml,
+}
+
+#[derive(Args, Debug)]
+pub struct ConvertArgs {
+    #[clap(help = "The file to convert. Reads from stdin if not given, or for -.")]
+    file: Option<PathBuf>,
+    #[clap(
+        long,
+        value_enum,
+        help = "The format of the input. Defaults to the format matching the file extension, or dent."
+    )]
+    from: Option<Format>,
+    #[clap(long, value_enum, help = "The format to write.")]
+    to: Format,
+    #[clap(long, help = "Write the output on a single line.")]
+    compact: bool,
+}
+
+pub fn run(args: ConvertArgs, dent: &Dent) -> ! {
+    let file = args.file.filter(|f| f.as_os_str() != "-");
+    let from = args.from.unwrap_or_else(|| match &file {
+        Some(file) => match file.extension().and_then(|e| e.to_str()) {
+            Some("json") => Format::Json,
+            Some("yaml" | "yml") => Format::Yaml,
+            Some("toml") => Format::Toml,
+            _ => Format::Dent,
+        },
+        None => Format::Dent,
+    });
+
+    let value = read(dent, file, from);
+
+    let output = match (args.to, args.compact) {
+        (Format::Dent, false) => Emitter::new().emit(&value),
+        (Format::Dent, true) => Emitter::compact().with_trailing_newline(true).emit(&value),
+        (Format::Json, false) => json::to_string_pretty(&value)
+            .map(|s| s + "\n")
+            .unwrap_or_else(|e| fail(e, None)),
+        (Format::Json, true) => json::to_string(&value)
+            .map(|s| s + "\n")
+            .unwrap_or_else(|e| fail(e, None)),
+        (Format::Yaml, _) => yaml::to_string(&value),
+        (Format::Toml, _) => {
+            let converted = toml::to_string(&value).unwrap_or_else(|e| fail(e, None));
+            warn(&converted, "");
+            converted.value
+        }
+    };
+    print!("{}", output);
+    std::process::exit(0);
+}
+
+/// Reads a value from a file, or from stdin if there is none.
+fn read(dent: &Dent, file: Option<PathBuf>, format: Format) -> OwnedValue {
+    if let (Format::Dent, Some(file)) = (format, &file) {
+        // Parse the file itself, so imports are found and errors point at it
+        return dent.parse_file(file).unwrap_or_else(|e| fail(e, None));
+    }
+
+    let source = match &file {
+        Some(file) => std::fs::read_to_string(file).unwrap_or_else(|e| {
+            eprintln!("{}: {}", file.display(), e);
+            std::process::exit(1);
+        }),
+        None => {
+            let mut source = String::new();
+            std::io::stdin().read_to_string(&mut source).unwrap();
+            source
+        }
+    };
+
+    match format {
+        Format::Dent => dent
+            .parse(&source)
+            .map(|v| v.into_owned())
+            .unwrap_or_else(|e| fail(e, Some(&source))),
+        Format::Json => json::from_str(&source).unwrap_or_else(|e| fail(e, Some(&source))),
+        Format::Yaml => {
+            let converted = yaml::from_str(&source).unwrap_or_else(|e| fail(e, Some(&source)));
+            warn(&converted, &source);
+            converted.value
+        }
+        Format::Toml => {
+            let converted = toml::from_str(&source).unwrap_or_else(|e| fail(e, Some(&source)));
+            warn(&converted, &source);
+            converted.value
+        }
+    }
+}
+
+/// Reports the warnings of a conversion, which are located in `source` if
+/// they have a location.
+fn warn<T>(converted: &Converted<T>, source: &str) {
+    for warning in &converted.warnings {
+        eprint!("{}", Diagnostic::from(warning).render(source));
+    }
+}
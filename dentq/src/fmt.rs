@@ -0,0 +1,122 @@
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::Args;
+use dent_parse::{document::Document, Emitter};
+use similar::TextDiff;
+
+use crate::{fail, report};
+
+#[derive(Args, Debug)]
+pub struct FmtArgs {
+    #[clap(
+        help = "The dent files or directories to format. Directories are searched for .dent files. Reads from stdin and writes to stdout if none are given, or for -."
+    )]
+    paths: Vec<PathBuf>,
+    #[clap(
+        long,
+        help = "Check that the files are formatted instead of formatting them. Prints a diff and exits with an error for unformatted files."
+    )]
+    check: bool,
+    #[clap(long, help = "Sort dictionary keys.")]
+    sort_keys: bool,
+}
+
+pub fn run(args: FmtArgs) -> ! {
+    let emitter = Emitter::new().with_sort_keys(args.sort_keys);
+
+    if args.paths.is_empty() || args.paths.iter().all(|p| p.as_os_str() == "-") {
+        let mut source = String::new();
+        std::io::stdin().read_to_string(&mut source).unwrap();
+
+        let document = Document::parse(&source).unwrap_or_else(|e| fail(e, Some(&source)));
+        let formatted = document.format(&emitter);
+
+        if args.check {
+            if formatted != source {
+                print_diff("<stdin>", &source, &formatted);
+                std::process::exit(1);
+            }
+        } else {
+            std::io::stdout().write_all(formatted.as_bytes()).unwrap();
+        }
+        std::process::exit(0);
+    }
+
+    let mut files = Vec::new();
+    for path in &args.paths {
+        if path.is_dir() {
+            find_dent_files(path, &mut files);
+        } else {
+            files.push(path.clone());
+        }
+    }
+
+    let mut failed = false;
+
+    for file in files {
+        let document = match Document::parse_file(&file) {
+            Ok(document) => document,
+            Err(dent_parse::Error::Io(kind)) => {
+                eprintln!("{}: {}", file.display(), std::io::Error::from(kind));
+                failed = true;
+                continue;
+            }
+            Err(e) => {
+                report(&e, None);
+                failed = true;
+                continue;
+            }
+        };
+
+        // Documents are lossless, so this is the contents of the file
+        let source = document.to_string();
+        let formatted = document.format(&emitter);
+
+        if formatted == source {
+            continue;
+        }
+
+        if args.check {
+            print_diff(&file.display().to_string(), &source, &formatted);
+            failed = true;
+        } else if let Err(e) = std::fs::write(&file, formatted) {
+            eprintln!("{}: {}", file.display(), e);
+            failed = true;
+        }
+    }
+
+    std::process::exit(if failed { 1 } else { 0 });
+}
+
+/// Adds the `.dent` files in a directory and its subdirectories to `files`, in
+/// alphabetical order.
+fn find_dent_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let mut entries: Vec<PathBuf> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(e) => {
+            eprintln!("{}: {}", dir.display(), e);
+            return;
+        }
+    };
+    entries.sort();
+
+    for path in entries {
+        if path.is_dir() {
+            find_dent_files(&path, files);
+        } else if path.extension().is_some_and(|e| e == "dent") {
+            files.push(path);
+        }
+    }
+}
+
+fn print_diff(name: &str, source: &str, formatted: &str) {
+    print!(
+        "{}",
+        TextDiff::from_lines(source, formatted)
+            .unified_diff()
+            .header(name, name)
+    );
+}
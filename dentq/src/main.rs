@@ -1,82 +1,148 @@
+mod convert;
+mod fmt;
+mod output;
+mod validate;
+
 use std::{io::Read, path::PathBuf};
 
-use dent_parse::{Dent, Value};
+use dent_parse::{diagnostics::Diagnostic, query::Pipeline, Dent};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use output::OutputFormat;
 
 #[derive(Parser, Debug)]
 #[clap(
     name = "dentq",
-    about = "A simple command line tool for querying dent files."
+    about = "A simple command line tool for querying dent files.",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
 )]
 struct Cli {
-    #[clap(help = "The dent file to query.")]
-    file: PathBuf,
-    #[clap(help = "The query to run. For example: .foo.bar[0].baz")]
-    query: String,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    #[clap(required = true, help = "The dent file to query.")]
+    file: Option<PathBuf>,
+    #[clap(
+        required = true,
+        help = "The query to run. For example: .foo.bar[0].baz or ..name or .list[?(.age > 30)] or .list | map(.name)"
+    )]
+    query: Option<String>,
+    #[clap(
+        short,
+        long,
+        value_enum,
+        default_value_t,
+        help = "The format to print results in."
+    )]
+    output: OutputFormat,
+    #[clap(
+        short,
+        long,
+        help = "Print strings without quotes, for use in shell scripts."
+    )]
+    raw: bool,
+    #[clap(
+        short = 'I',
+        long = "include",
+        value_name = "DIR",
+        global = true,
+        help = "A directory to search for imported files in, after the importing file's directory. Can be given several times, and is searched before the directories in DENT_PATH."
+    )]
+    include_dirs: Vec<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    #[clap(about = "Formats dent files in the canonical style.")]
+    Fmt(fmt::FmtArgs),
+    #[clap(about = "Converts between dent, JSON, YAML and TOML.")]
+    Convert(convert::ConvertArgs),
+    #[clap(about = "Checks that a dent file matches a schema, reporting every violation.")]
+    Validate(validate::ValidateArgs),
 }
 
 fn main() {
     let args = Cli::parse();
-    let dent = Dent::default();
+    let dent = parser(&args.include_dirs);
+
+    match args.command {
+        Some(Command::Fmt(args)) => fmt::run(args),
+        Some(Command::Convert(args)) => convert::run(args, &dent),
+        Some(Command::Validate(args)) => validate::run(args, &dent),
+        None => run_query(
+            &dent,
+            args.file.unwrap(),
+            args.query.unwrap(),
+            args.output,
+            args.raw,
+        ),
+    }
+}
+
+/// Creates the parser every command uses, which imports from the include
+/// directories given on the command line and in `DENT_PATH`.
+fn parser(include_dirs: &[PathBuf]) -> Dent {
+    let mut dent = Dent::default();
+    for dir in include_dirs {
+        dent.add_include_dir(dir);
+    }
+    dent.add_include_dirs_from_env();
+    dent
+}
 
-    if args.file == PathBuf::from("-") {
+fn run_query(dent: &Dent, file: PathBuf, query_string: String, format: OutputFormat, raw: bool) {
+    let query = Pipeline::parse(&query_string).unwrap_or_else(|e| fail(e, Some(&query_string)));
+
+    if file.as_os_str() == "-" {
         let stdin = std::io::stdin();
         let mut handle = stdin.lock();
         let mut buffer = String::new();
         handle.read_to_string(&mut buffer).unwrap();
 
-        let v = dent.parse(&buffer).unwrap();
+        let v = dent
+            .parse(&buffer)
+            .unwrap_or_else(|e| fail(e, Some(&buffer)));
 
-        let result = query(&v, &args.query);
-        println!("{}", result);
+        let results = query
+            .run(&v)
+            .unwrap_or_else(|e| fail(e, Some(&query_string)));
+        output::print_results(&results, format, raw);
     } else {
-        if !args.file.exists() {
-            eprintln!("File does not exist: {:?}", args.file);
+        if !file.exists() {
+            eprintln!("File does not exist: {:?}", file);
             std::process::exit(1);
         }
 
-        let v = dent.parse_file(&args.file).unwrap();
+        let v = dent.parse_file(&file).unwrap_or_else(|e| fail(e, None));
 
-        let result = query(&v, &args.query);
-        println!("{}", result);
+        let results = query
+            .run(&v)
+            .unwrap_or_else(|e| fail(e, Some(&query_string)));
+        output::print_results(&results, format, raw);
     }
 }
 
-enum QueryPart {
-    Key(String),
-    Index(usize),
+/// Reports a parse error and exits.
+fn fail(error: dent_parse::Error, source: Option<&str>) -> ! {
+    report(&error, source);
+    std::process::exit(1);
 }
 
-fn query(value: &Value, query: &str) -> String {
-    let parts = query.split('.');
-    let mut query_parts = Vec::new();
-
-    for part in parts.filter(|p| !p.is_empty()) {
-        if part.contains('[') {
-            let mut part_parts = part.split('[');
-            let key = part_parts.next().unwrap();
-            let index = part_parts.next().unwrap().replace(']', "");
-            let index = index.parse::<usize>().unwrap();
-            query_parts.push(QueryPart::Key(key.to_string()));
-            query_parts.push(QueryPart::Index(index));
-        } else {
-            query_parts.push(QueryPart::Key(part.to_string()));
-        }
-    }
+/// Reports a parse error.
+///
+/// `source` is the text that was parsed, if it did not come from a file. For
+/// errors in files, the file the error points at is read again to render it,
+/// which for errors in imported files is the imported file.
+fn report(error: &dent_parse::Error, source: Option<&str>) {
+    let diagnostic = Diagnostic::from(error);
 
-    let mut result = value.clone();
+    let file_source = diagnostic
+        .primary
+        .as_ref()
+        .and_then(|l| l.location.file.as_ref())
+        .and_then(|f| std::fs::read_to_string(f).ok());
 
-    for part in query_parts {
-        match part {
-            QueryPart::Key(key) => {
-                result = result[key.as_str()].clone();
-            }
-            QueryPart::Index(index) => {
-                result = result[index].clone();
-            }
-        }
-    }
+    let source = file_source.as_deref().or(source).unwrap_or("");
 
-    result.to_string()
+    eprint!("{}", diagnostic.render(source));
 }
@@ -0,0 +1,49 @@
+use clap::ValueEnum;
+use dent_parse::{json, yaml, Emitter, OwnedValue, Result, Value};
+
+use crate::fail;
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum OutputFormat {
+    /// Dent, splitting long lists and dictionaries over multiple lines
+    #[default]
+    Dent,
+    /// Dent, with every result on a single line
+    DentCompact,
+    /// JSON, with every result on a single line
+    Json,
+    /// Indented JSON
+    JsonPretty,
+    /// YAML, with a document for every result
+    Yaml,
+    /// Like dent-compact, but strings are printed without quotes
+    Raw,
+}
+
+/// Prints the results of a query, each followed by a line break.
+///
+/// With `raw`, results that are strings are printed as they are, without
+/// quotes or escapes, whatever the format.
+pub fn print_results(results: &[OwnedValue], format: OutputFormat, raw: bool) {
+    for result in results {
+        if format == OutputFormat::Yaml && results.len() > 1 {
+            println!("---");
+        }
+        let output = format_value(result, format, raw).unwrap_or_else(|e| fail(e, None));
+        println!("{}", output);
+    }
+}
+
+fn format_value(value: &Value, format: OutputFormat, raw: bool) -> Result<String> {
+    if let (true, Value::Str(s)) = (raw || format == OutputFormat::Raw, value) {
+        return Ok(s.to_string());
+    }
+
+    Ok(match format {
+        OutputFormat::Dent => Emitter::new().with_trailing_newline(false).emit(value),
+        OutputFormat::DentCompact | OutputFormat::Raw => Emitter::compact().emit(value),
+        OutputFormat::Json => json::to_string(value)?,
+        OutputFormat::JsonPretty => json::to_string_pretty(value)?,
+        OutputFormat::Yaml => yaml::to_string(value).trim_end().to_string(),
+    })
+}
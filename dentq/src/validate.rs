@@ -0,0 +1,70 @@
+use std::{io::Read, path::PathBuf};
+
+use clap::Args;
+use dent_parse::{diagnostics::Diagnostic, schema::Schema, Dent, Emitter};
+
+use crate::fail;
+
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    #[clap(long, help = "The dent file with the schema to validate against.")]
+    schema: PathBuf,
+    #[clap(help = "The dent file to validate. Reads from stdin if not given, or for -.")]
+    file: Option<PathBuf>,
+    #[clap(
+        long,
+        help = "Print the file with the defaults of the schema filled in and its values coerced to the types of the schema."
+    )]
+    normalize: bool,
+}
+
+pub fn run(args: ValidateArgs, dent: &Dent) -> ! {
+    let schema = dent
+        .parse_file(&args.schema)
+        .and_then(|value| Schema::from_value(&value))
+        .unwrap_or_else(|e| fail(e, None));
+
+    let (source, parsed) = match args.file.filter(|f| f.as_os_str() != "-") {
+        Some(file) => {
+            let parsed = dent
+                .parse_file_with_locations(&file)
+                .unwrap_or_else(|e| fail(e, None));
+            (std::fs::read_to_string(&file).unwrap_or_default(), parsed)
+        }
+        None => {
+            let mut source = String::new();
+            std::io::stdin().read_to_string(&mut source).unwrap();
+            let parsed = dent
+                .parse_with_locations(&source)
+                .map(|(value, locations)| (value.into_owned(), locations))
+                .unwrap_or_else(|e| fail(e, Some(&source)));
+            (source, parsed)
+        }
+    };
+    let (value, locations) = parsed;
+
+    let violations = if args.normalize {
+        match schema.normalize_located(&value, &locations) {
+            Ok(normalized) => {
+                print!("{}", Emitter::new().emit(&normalized));
+                Vec::new()
+            }
+            Err(violations) => violations,
+        }
+    } else {
+        schema.validate_located(&value, &locations)
+    };
+    for violation in &violations {
+        eprint!("{}", Diagnostic::from(violation).render(&source));
+    }
+
+    if violations.is_empty() {
+        std::process::exit(0);
+    }
+    eprintln!(
+        "{} violation{} of the schema",
+        violations.len(),
+        if violations.len() == 1 { "" } else { "s" }
+    );
+    std::process::exit(1);
+}